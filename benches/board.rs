@@ -0,0 +1,76 @@
+//! Measures the cost of a move on a 19x19 board at different stages of a game, which should stay about the same.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use druidgo::board::Board;
+use druidgo::game::{BoardSize, Stone};
+
+const SIZE: usize = 19;
+const STAGE: usize = 50;
+
+/// A random game of legal moves that do not fill single-point eyes, as points and colors.
+fn random_game(len: usize) -> Vec<(usize, Stone)> {
+    let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut board = Board::new(BoardSize::square(SIZE));
+    let mut color = Stone::Black;
+    let mut moves = vec![];
+
+    while moves.len() < len {
+        let candidates: Vec<usize> = (0..board.len())
+            .filter(|&i| {
+                board[i].is_none()
+                    && board.ko() != Some(i)
+                    && !board.is_suicide(i, color)
+                    && !board.neighbors(i).iter().all(|&n| board[n] == Some(color))
+            })
+            .collect();
+        if candidates.is_empty() {
+            break;
+        }
+
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        let i = candidates[(seed % candidates.len() as u64) as usize];
+
+        board.play(i, color);
+        moves.push((i, color));
+        color = -color;
+    }
+
+    moves
+}
+
+fn play(c: &mut Criterion) {
+    let moves = random_game(6 * STAGE);
+    let mut group = c.benchmark_group("play 19x19");
+
+    for stage in (0..moves.len()).step_by(STAGE) {
+        let mut board = Board::new(BoardSize::square(SIZE));
+        for &(i, color) in &moves[..stage] {
+            board.play(i, color);
+        }
+        let next = &moves[stage..(stage + STAGE).min(moves.len())];
+
+        group.throughput(Throughput::Elements(next.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::new("moves", format!("{}-{}", stage, stage + next.len())),
+            next,
+            |b, next| {
+                b.iter_batched_ref(
+                    || board.clone(),
+                    |board| {
+                        for &(i, color) in next {
+                            board.play(i, color);
+                        }
+                    },
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, play);
+criterion_main!(benches);
@@ -0,0 +1,166 @@
+//! Benson's algorithm, which finds the chains that cannot be captured even if their owner never answers a
+//! single move of the opponent.
+
+use crate::board::Board;
+use crate::game::{GameState, Pos, Stone};
+use enum_map::EnumMap;
+
+/// What Benson's algorithm proves alive for one color.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PassAlive {
+    /// The stones of each unconditionally alive chain.
+    pub chains: Vec<Vec<Pos>>,
+    /// The points of each region enclosed by those chains, where the opponent can never live: empty points,
+    /// and opponent stones that are as good as captured.
+    pub regions: Vec<Vec<Pos>>,
+}
+
+/// Finds the pass-alive chains and regions of both colors.
+pub fn pass_alive(state: &GameState) -> EnumMap<Stone, PassAlive> {
+    EnumMap::from_fn(|color| pass_alive_for(&state.board, color))
+}
+
+/// A maximal connected set of points without a stone of the color being examined.
+struct Region {
+    points: Vec<usize>,
+    /// Heads of the chains around the region.
+    borders: Vec<usize>,
+    /// Heads of the chains that have every empty point of the region as a liberty.
+    vital_to: Vec<usize>,
+}
+
+fn pass_alive_for(board: &Board, color: Stone) -> PassAlive {
+    let mut region_of = vec![None; board.len()];
+    let mut regions: Vec<Region> = vec![];
+
+    for start in 0..board.len() {
+        if board[start] == Some(color) || region_of[start].is_some() {
+            continue;
+        }
+
+        let id = regions.len();
+        let mut region = Region {
+            points: vec![],
+            borders: vec![],
+            vital_to: vec![],
+        };
+        let mut todo = vec![start];
+        region_of[start] = Some(id);
+        while let Some(i) = todo.pop() {
+            region.points.push(i);
+            for &n in board.neighbors(i) {
+                if board[n] == Some(color) {
+                    let head = board.chain_head(n);
+                    if !region.borders.contains(&head) {
+                        region.borders.push(head);
+                    }
+                } else if region_of[n].is_none() {
+                    region_of[n] = Some(id);
+                    todo.push(n);
+                }
+            }
+        }
+
+        let empty: Vec<usize> = region
+            .points
+            .iter()
+            .copied()
+            .filter(|&i| board[i].is_none())
+            .collect();
+        region.vital_to = region
+            .borders
+            .iter()
+            .copied()
+            .filter(|&head| {
+                empty.iter().all(|&i| {
+                    board
+                        .neighbors(i)
+                        .iter()
+                        .any(|&n| board[n] == Some(color) && board.chain_head(n) == head)
+                })
+            })
+            .collect();
+
+        regions.push(region);
+    }
+
+    let mut chains: Vec<usize> = (0..board.len())
+        .filter(|&i| board[i] == Some(color) && board.chain_head(i) == i)
+        .collect();
+    let mut alive_regions: Vec<usize> = (0..regions.len()).collect();
+
+    // drop the chains with fewer than two vital regions left, and the regions around which a chain was dropped,
+    // until nothing changes
+    loop {
+        let before = (chains.len(), alive_regions.len());
+
+        chains.retain(|head| {
+            alive_regions
+                .iter()
+                .filter(|&&r| regions[r].vital_to.contains(head))
+                .count()
+                >= 2
+        });
+        alive_regions.retain(|&r| regions[r].borders.iter().all(|head| chains.contains(head)));
+
+        if (chains.len(), alive_regions.len()) == before {
+            break;
+        }
+    }
+
+    PassAlive {
+        chains: chains
+            .iter()
+            .map(|&head| board.chain(head).map(|i| board.pos(i)).collect())
+            .collect(),
+        // a region no living chain has every empty point next to may still be big enough for the opponent to live in
+        regions: alive_regions
+            .iter()
+            .filter(|&&r| regions[r].vital_to.iter().any(|head| chains.contains(head)))
+            .map(|&r| regions[r].points.iter().map(|&i| board.pos(i)).collect())
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::game;
+
+    #[test]
+    fn two_eyes_are_pass_alive() {
+        let game = game(
+            "
+            . X . X .
+            X X X X X
+            . . . . .
+            . . . . .
+            . . . . .
+            ",
+        );
+        let alive = pass_alive(&game.state);
+        assert_eq!(alive[Stone::Black].chains.len(), 1);
+        assert_eq!(alive[Stone::Black].chains[0].len(), 7);
+        for eye in [Pos(0, 0), Pos(2, 0)] {
+            assert!(alive[Stone::Black]
+                .regions
+                .iter()
+                .any(|region| region.contains(&eye)));
+        }
+        assert_eq!(alive[Stone::White], PassAlive::default());
+    }
+
+    #[test]
+    fn one_eye_is_not() {
+        let game = game(
+            "
+            . X . . .
+            X X . . .
+            . . . . .
+            . . . . .
+            . . . . .
+            ",
+        );
+        assert_eq!(pass_alive(&game.state)[Stone::Black], PassAlive::default());
+    }
+}
@@ -0,0 +1,356 @@
+//! A headless Go Text Protocol (GTP v2) engine over stdin/stdout, for GoGui, Sabaki and match scripts.
+//!
+//! It searches with Monte Carlo tree search, which these options tune:
+//!
+//! - `--playouts <n>`: playouts per move
+//! - `--time <seconds>`: thinking time per move instead
+//! - `--threads <n>`: threads searching together, by default one per core
+//! - `--random`: play random moves instead, e.g. to test a controller
+
+use druidgo::coords::{CoordError, Notation, GTP_COLUMNS};
+use druidgo::diagram::Diagram;
+use druidgo::estimate;
+use druidgo::game::{BoardSize, Game, Move, Stone};
+use druidgo::handicap::{self, Placement};
+use druidgo::mcts::{Budget, Config, MctsPlayer};
+use druidgo::player::{Player, RandomPlayer};
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+/// Random games to tell dead stones apart with for `final_score`; more than the board UI uses, as the score
+/// decides the game.
+const PLAYOUTS: usize = 256;
+
+const COMMANDS: &[&str] = &[
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "time_settings",
+    "fixed_handicap",
+    "place_free_handicap",
+    "set_free_handicap",
+    "play",
+    "genmove",
+    "undo",
+    "showboard",
+    "final_score",
+];
+
+fn parse_color(s: &str) -> Result<Stone, String> {
+    match s.to_lowercase().as_str() {
+        "b" | "black" => Ok(Stone::Black),
+        "w" | "white" => Ok(Stone::White),
+        _ => Err("syntax error".to_string()),
+    }
+}
+
+fn parse_vertex(s: &str, size: BoardSize) -> Result<Move, String> {
+    if s.eq_ignore_ascii_case("pass") {
+        return Ok(Move::Pass);
+    }
+
+    match Notation::Gtp.parse(s, size) {
+        Ok(p) => Ok(Move::Place(p)),
+        Err(CoordError::Syntax(_)) => Err("syntax error".to_string()),
+        Err(CoordError::OffBoard(_)) => Err("illegal move".to_string()),
+    }
+}
+
+fn format_vertex(mv: Move, size: BoardSize) -> String {
+    // `boardsize` keeps the board small enough to name every point
+    Notation::Gtp.format_move(mv, size).unwrap()
+}
+
+struct Engine {
+    game: Game,
+    /// Decides on the moves for `genmove`.
+    player: Box<dyn Player>,
+}
+
+impl Engine {
+    fn new(player: Box<dyn Player>) -> Self {
+        Self {
+            game: Game::new(19),
+            player,
+        }
+    }
+
+    /// Starts a new game, keeping the settings the controller made.
+    fn clear(&mut self, size: BoardSize) {
+        let info = self.game.info.clone();
+        self.game = Game::with_ruleset(size, self.game.ruleset);
+        self.game.info.komi = info.komi;
+        self.game.info.time_limit = info.time_limit;
+        self.game.info.overtime = info.overtime;
+    }
+
+    /// Plays `mv` for `color`, regardless of whose turn it is, as GTP controllers expect.
+    fn play(&mut self, color: Stone, mv: Move) -> Result<(), String> {
+        let (turn, status) = (self.game.turn, self.game.status);
+        // resuming drops the result, which a move that cannot be played should leave alone
+        let result = self.game.info.result.clone();
+
+        self.game.resume();
+        self.game.set_turn(color);
+        match self.game.play(mv) {
+            Ok(_) => Ok(()),
+            Err(reason) => {
+                self.game.set_turn(turn);
+                self.game.status = status;
+                self.game.info.result = result;
+                Err(format!("illegal move ({})", reason))
+            }
+        }
+    }
+
+    /// Lets the player decide on a move for `color` and plays it, failing if it cannot be played, e.g. after the
+    /// game ended.
+    fn generate(&mut self, color: Stone) -> Result<Move, String> {
+        let (turn, status) = (self.game.turn, self.game.status);
+        let result = self.game.info.result.clone();
+
+        self.game.resume();
+        self.game.set_turn(color);
+        let mv = self.player.genmove(&self.game);
+        self.game.set_turn(turn);
+        self.game.status = status;
+        self.game.info.result = result;
+
+        self.play(color, mv)?;
+        Ok(mv)
+    }
+
+    /// Gives Black `stones` handicap stones, returning where they went.
+    fn handicap(&mut self, stones: &str, placement: Placement) -> Result<String, String> {
+        let stones: usize = stones.parse().map_err(|_| "syntax error")?;
+        if self.game.move_number() > 0 || self.game.state.board.iter().any(Option::is_some) {
+            return Err("board not empty".to_string());
+        }
+        // moves that were taken back would not follow from the handicap
+        self.clear(self.game.size);
+        // the controller sets komi itself
+        let komi = self.game.info.komi;
+        if !self.game.set_handicap(stones, placement) {
+            return Err("invalid number of stones".to_string());
+        }
+        self.game.info.komi = komi;
+        Ok(self.stones())
+    }
+
+    /// Gives Black handicap stones on `moves`, which must all be points, returning where they went.
+    fn free_handicap(&mut self, moves: &[Move]) -> Result<String, String> {
+        self.handicap(&moves.len().to_string(), Placement::Free)?;
+        for &mv in moves {
+            if !matches!(mv, Move::Place(_)) || self.play(Stone::Black, mv).is_err() {
+                self.clear(self.game.size);
+                return Err("bad vertex list".to_string());
+            }
+        }
+        Ok(self.stones())
+    }
+
+    /// The points with a stone on them, as vertices.
+    fn stones(&self) -> String {
+        let size = self.game.size;
+        let vertices: Vec<String> = size
+            .points()
+            .filter(|&p| self.game.has_stone_at(p))
+            .map(|p| format_vertex(Move::Place(p), size))
+            .collect();
+        vertices.join(" ")
+    }
+
+    fn showboard(&self) -> String {
+        let mut out = format!("\n{:#}", Diagram::from_game(&self.game));
+        out += &format!(
+            "Captures: {} white, {} black",
+            self.game.state.captures[Stone::White],
+            self.game.state.captures[Stone::Black]
+        );
+        out
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        let arg = |i: usize| {
+            args.get(i)
+                .copied()
+                .ok_or_else(|| "syntax error".to_string())
+        };
+
+        match command {
+            "protocol_version" => Ok("2".to_string()),
+            "name" => Ok(env!("CARGO_PKG_NAME").to_string()),
+            "version" => Ok(env!("CARGO_PKG_VERSION").to_string()),
+            "known_command" => Ok(COMMANDS.contains(&arg(0)?).to_string()),
+            "list_commands" => Ok(COMMANDS.join("\n")),
+            "quit" => Ok(String::new()),
+            "boardsize" => {
+                let size: usize = arg(0)?.parse().map_err(|_| "syntax error")?;
+                if !(2..=GTP_COLUMNS.len()).contains(&size) {
+                    return Err("unacceptable size".to_string());
+                }
+                self.clear(BoardSize::square(size));
+                Ok(String::new())
+            }
+            "clear_board" => {
+                self.clear(self.game.size);
+                Ok(String::new())
+            }
+            "komi" => {
+                self.game.info.komi = arg(0)?.parse().map_err(|_| "syntax error")?;
+                Ok(String::new())
+            }
+            "time_settings" => {
+                let main_time: f64 = arg(0)?.parse().map_err(|_| "syntax error")?;
+                let byo_yomi_time: f64 = arg(1)?.parse().map_err(|_| "syntax error")?;
+                let byo_yomi_stones: usize = arg(2)?.parse().map_err(|_| "syntax error")?;
+
+                let info = &mut self.game.info;
+                if byo_yomi_time > 0.0 && byo_yomi_stones == 0 {
+                    // the protocol's way of saying there are no time limits
+                    info.time_limit = None;
+                    info.overtime = String::new();
+                } else {
+                    info.time_limit = Some(main_time);
+                    info.overtime = if byo_yomi_stones > 0 {
+                        format!("{}/{} Canadian", byo_yomi_stones, byo_yomi_time)
+                    } else {
+                        String::new()
+                    };
+                }
+                Ok(String::new())
+            }
+            "fixed_handicap" => self.handicap(arg(0)?, Placement::Fixed),
+            "place_free_handicap" => {
+                let stones: usize = arg(0)?.parse().map_err(|_| "syntax error")?;
+                let points = handicap::spread_points(self.game.size, stones)
+                    .ok_or("invalid number of stones")?;
+                let moves: Vec<Move> = points.into_iter().map(Move::Place).collect();
+                self.free_handicap(&moves)
+            }
+            "set_free_handicap" => {
+                let moves = args
+                    .iter()
+                    .map(|&vertex| parse_vertex(vertex, self.game.size))
+                    .collect::<Result<Vec<_>, _>>()?;
+                self.free_handicap(&moves)?;
+                Ok(String::new())
+            }
+            "play" => {
+                let color = parse_color(arg(0)?)?;
+                let mv = parse_vertex(arg(1)?, self.game.size)?;
+                self.play(color, mv)?;
+                Ok(String::new())
+            }
+            "genmove" => {
+                let color = parse_color(arg(0)?)?;
+                let mv = self.generate(color)?;
+                Ok(format_vertex(mv, self.game.size))
+            }
+            "undo" => {
+                if self.game.undo() {
+                    Ok(String::new())
+                } else {
+                    Err("cannot undo".to_string())
+                }
+            }
+            "showboard" => Ok(self.showboard()),
+            "final_score" => {
+                let estimate = estimate::estimate(&self.game.state, self.game.turn, PLAYOUTS);
+                let score = estimate.score(&self.game);
+                Ok(score.result(self.game.ruleset.scoring()).to_string())
+            }
+            _ => Err("unknown command".to_string()),
+        }
+    }
+}
+
+/// The player the command line asks for.
+fn player() -> Result<Box<dyn Player>, String> {
+    let mut config = Config::default();
+    let mut random = false;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .and_then(|value| value.parse::<f64>().ok())
+                .filter(|&value| value > 0.0)
+                .ok_or(format!("{} needs a positive number", arg))
+        };
+        match arg.as_str() {
+            "--playouts" => config.budget = Budget::Playouts(value()? as usize),
+            "--time" => config.budget = Budget::Time(Duration::from_secs_f64(value()?)),
+            "--threads" => config.threads = value()? as usize,
+            "--random" => random = true,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    if random {
+        Ok(Box::new(RandomPlayer::default()))
+    } else {
+        Ok(Box::new(MctsPlayer::new(config)))
+    }
+}
+
+fn main() -> io::Result<()> {
+    let player = match player() {
+        Ok(player) => player,
+        Err(message) => {
+            eprintln!("{}", message);
+            std::process::exit(2);
+        }
+    };
+    let mut engine = Engine::new(player);
+    let mut stdout = io::stdout();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+
+        // strip comments and control characters, as the protocol asks
+        let line: String = line
+            .split('#')
+            .next()
+            .unwrap_or("")
+            .chars()
+            .map(|c| if c == '\t' { ' ' } else { c })
+            .filter(|c| !c.is_control())
+            .collect();
+
+        let mut words: Vec<&str> = line.split_whitespace().collect();
+        if words.is_empty() {
+            continue;
+        }
+
+        let id = match words[0].parse::<u32>() {
+            Ok(id) => {
+                words.remove(0);
+                id.to_string()
+            }
+            Err(_) => String::new(),
+        };
+
+        let command = match words.first() {
+            Some(&command) => command,
+            None => continue,
+        };
+
+        match engine.execute(command, &words[1..]) {
+            Ok(response) => write!(stdout, "={} {}\n\n", id, response)?,
+            Err(message) => write!(stdout, "?{} {}\n\n", id, message)?,
+        }
+        stdout.flush()?;
+
+        if command == "quit" {
+            break;
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,542 @@
+use crate::game::{BoardSize, Pos, Stone};
+use crate::topology::Topology;
+use crate::zobrist;
+use std::ops::Index;
+use std::sync::Arc;
+
+/// The stones on the board, together with the chains they form, so that playing a move only costs time
+/// in proportion to the chains it touches.
+///
+/// The stones of a chain are linked into a circular list through `next`, and each of them knows the head
+/// of its chain. The head keeps the chain's length and its number of pseudo-liberties: the number of
+/// (stone, empty neighbor) pairs. A liberty next to two stones of the chain counts twice, but the count
+/// is zero exactly when the chain has no liberties at all.
+#[derive(Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SavedBoard", try_from = "SavedBoard")
+)]
+pub struct Board {
+    size: BoardSize,
+    stones: Vec<Option<Stone>>,
+    next: Vec<usize>,
+    head: Vec<usize>,
+    chain_len: Vec<usize>,
+    liberties: Vec<usize>,
+    /// The point where the last move captured a single stone in a ko, which may not be retaken right away.
+    ko: Option<usize>,
+    /// The Zobrist hash of the stones, see `zobrist`.
+    hash: u64,
+    /// The neighbors of every point, shared between all copies of the board.
+    neighbors: Arc<Vec<Vec<usize>>>,
+}
+
+/// What was taken off the board by a move.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Removed {
+    pub captured: Vec<usize>,
+    pub suicide: Vec<usize>,
+}
+
+impl Board {
+    pub fn new(size: BoardSize) -> Self {
+        Self::with_topology(size, &Topology::Grid)
+    }
+
+    /// A board whose points are connected by `topology`.
+    ///
+    /// # Panics
+    ///
+    /// If `topology` is a graph that does not fit `size`, see `Topology::fits`.
+    pub fn with_topology(size: BoardSize, topology: &Topology) -> Self {
+        assert!(
+            topology.fits(size),
+            "{} topology does not fit a board of {}",
+            topology,
+            size
+        );
+        let len = size.len();
+
+        Self {
+            size,
+            stones: vec![None; len],
+            next: (0..len).collect(),
+            head: (0..len).collect(),
+            chain_len: vec![0; len],
+            liberties: vec![0; len],
+            ko: None,
+            hash: 0,
+            neighbors: topology.neighbor_table(size),
+        }
+    }
+
+    pub fn size(&self) -> BoardSize {
+        self.size
+    }
+
+    /// Number of points on the board.
+    pub fn len(&self) -> usize {
+        self.stones.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stones.is_empty()
+    }
+
+    pub fn pos(&self, i: usize) -> Pos {
+        self.size.pos(i)
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Option<Stone>> {
+        self.stones.iter()
+    }
+
+    pub fn neighbors(&self, i: usize) -> &[usize] {
+        &self.neighbors[i]
+    }
+
+    pub fn ko(&self) -> Option<usize> {
+        self.ko
+    }
+
+    /// The Zobrist hash of the stones on the board. Equal boards have equal hashes.
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    /// The point at the head of the chain of the stone at `i`, which identifies the chain.
+    pub fn chain_head(&self, i: usize) -> usize {
+        self.head[i]
+    }
+
+    pub fn chain_len(&self, i: usize) -> usize {
+        self.chain_len[self.head[i]]
+    }
+
+    /// The pseudo-liberties of the chain of the stone at `i`; see `Board`.
+    pub fn pseudo_liberties(&self, i: usize) -> usize {
+        self.liberties[self.head[i]]
+    }
+
+    /// The stones of the chain that the stone at `i` belongs to, starting with `i`.
+    pub fn chain(&self, i: usize) -> impl Iterator<Item = usize> + '_ {
+        let mut current = Some(i);
+        std::iter::from_fn(move || {
+            let stone = current?;
+            let next = self.next[stone];
+            current = if next == i { None } else { Some(next) };
+            Some(stone)
+        })
+    }
+
+    /// The distinct empty points next to the chain of the stone at `i`. Takes time in proportion to the chain.
+    pub fn liberties(&self, i: usize) -> Vec<usize> {
+        let mut liberties = vec![];
+        for stone in self.chain(i) {
+            for &n in &self.neighbors[stone] {
+                if self.stones[n].is_none() && !liberties.contains(&n) {
+                    liberties.push(n);
+                }
+            }
+        }
+        liberties
+    }
+
+    /// Whether the empty point `i` is the last liberty of the chain with head `head`.
+    fn is_last_liberty(&self, head: usize, i: usize) -> bool {
+        let adjacent = self.neighbors[i]
+            .iter()
+            .filter(|&&n| self.stones[n].is_some() && self.head[n] == head)
+            .count();
+        self.liberties[head] == adjacent
+    }
+
+    /// The heads of the distinct chains of `color` next to point `i`.
+    fn adjacent_chains(&self, i: usize, color: Stone) -> impl Iterator<Item = usize> + '_ {
+        let neighbors = &self.neighbors[i];
+        (0..neighbors.len()).filter_map(move |k| {
+            let n = neighbors[k];
+            let head = self.head[n];
+            let seen = neighbors[..k]
+                .iter()
+                .any(|&m| self.stones[m] == Some(color) && self.head[m] == head);
+            (self.stones[n] == Some(color) && !seen).then_some(head)
+        })
+    }
+
+    /// The xor of the keys of all stones in the chain with head `head`.
+    fn chain_hash(&self, head: usize) -> u64 {
+        let color = self.stones[head].unwrap();
+        self.chain(head)
+            .fold(0, |hash, stone| hash ^ zobrist::stone(stone, color))
+    }
+
+    /// The hash the board would have after a stone of `color` is played at the empty point `i`, without playing it.
+    pub fn hash_after(&self, i: usize, color: Stone) -> u64 {
+        let mut hash = self.hash ^ zobrist::stone(i, color);
+        let mut captures = false;
+        for head in self.adjacent_chains(i, -color) {
+            if self.is_last_liberty(head, i) {
+                hash ^= self.chain_hash(head);
+                captures = true;
+            }
+        }
+
+        if !captures && self.is_suicide(i, color) {
+            hash = self.hash;
+            for head in self.adjacent_chains(i, color) {
+                hash ^= self.chain_hash(head);
+            }
+        }
+
+        hash
+    }
+
+    /// Whether a stone of `color` at the empty point `i` would capture anything.
+    pub fn captures(&self, i: usize, color: Stone) -> bool {
+        self.neighbors[i]
+            .iter()
+            .any(|&n| self.stones[n] == Some(-color) && self.is_last_liberty(self.head[n], i))
+    }
+
+    /// Whether a stone of `color` at the empty point `i` would leave its own chain without liberties.
+    pub fn is_suicide(&self, i: usize, color: Stone) -> bool {
+        self.neighbors[i].iter().all(|&n| match self.stones[n] {
+            None => false,
+            Some(c) if c == color => self.is_last_liberty(self.head[n], i),
+            Some(_) => !self.is_last_liberty(self.head[n], i),
+        })
+    }
+
+    /// Puts a stone of `color` on the empty point `i`, and takes off the opponent chains left without
+    /// liberties, and then its own chain if that has none either.
+    pub fn play(&mut self, i: usize, color: Stone) -> Removed {
+        debug_assert!(self.stones[i].is_none());
+
+        self.ko = None;
+        self.stones[i] = Some(color);
+        self.hash ^= zobrist::stone(i, color);
+        self.head[i] = i;
+        self.next[i] = i;
+        self.chain_len[i] = 1;
+        self.liberties[i] = 0;
+
+        for k in 0..self.neighbors[i].len() {
+            let n = self.neighbors[i][k];
+            match self.stones[n] {
+                None => self.liberties[i] += 1,
+                Some(_) => self.liberties[self.head[n]] -= 1,
+            }
+        }
+
+        for k in 0..self.neighbors[i].len() {
+            let n = self.neighbors[i][k];
+            if self.stones[n] == Some(color) && self.head[n] != self.head[i] {
+                self.merge(self.head[i], self.head[n]);
+            }
+        }
+
+        let mut removed = Removed::default();
+        for k in 0..self.neighbors[i].len() {
+            let n = self.neighbors[i][k];
+            if self.stones[n] == Some(-color) && self.liberties[self.head[n]] == 0 {
+                self.remove_chain(n, &mut removed.captured);
+            }
+        }
+
+        if self.liberties[self.head[i]] == 0 {
+            self.remove_chain(i, &mut removed.suicide);
+        } else if removed.captured.len() == 1
+            && self.chain_len[self.head[i]] == 1
+            && self.liberties[i] == 1
+        {
+            self.ko = Some(removed.captured[0]);
+        }
+
+        removed
+    }
+
+    /// Forgets the ko, as any move elsewhere does.
+    pub fn pass(&mut self) {
+        self.ko = None;
+    }
+
+    /// Reconnects the points for `topology`, which must fit the size of the board.
+    #[cfg(feature = "serde")]
+    pub(crate) fn set_topology(&mut self, topology: &Topology) {
+        let ko = self.ko;
+        self.neighbors = topology.neighbor_table(self.size);
+        self.rebuild();
+        self.ko = ko;
+    }
+
+    /// Puts or removes a stone without capturing anything, e.g. to set up a position.
+    pub fn set(&mut self, i: usize, stone: Option<Stone>) {
+        self.stones[i] = stone;
+        self.rebuild();
+    }
+
+    /// Joins the chains with heads `a` and `b`, relabeling the shorter one.
+    fn merge(&mut self, a: usize, b: usize) {
+        let (long, short) = if self.chain_len[a] >= self.chain_len[b] {
+            (a, b)
+        } else {
+            (b, a)
+        };
+
+        let mut stone = short;
+        loop {
+            self.head[stone] = long;
+            stone = self.next[stone];
+            if stone == short {
+                break;
+            }
+        }
+
+        // splices the two circular lists into one
+        self.next.swap(long, short);
+        self.chain_len[long] += self.chain_len[short];
+        self.liberties[long] += self.liberties[short];
+    }
+
+    fn remove_chain(&mut self, i: usize, removed: &mut Vec<usize>) {
+        let from = removed.len();
+        removed.extend(self.chain(i));
+
+        let color = self.stones[i].unwrap();
+        for &stone in &removed[from..] {
+            self.stones[stone] = None;
+            self.hash ^= zobrist::stone(stone, color);
+        }
+
+        for &stone in &removed[from..] {
+            for k in 0..self.neighbors[stone].len() {
+                let n = self.neighbors[stone][k];
+                if self.stones[n].is_some() {
+                    self.liberties[self.head[n]] += 1;
+                }
+            }
+            self.head[stone] = stone;
+            self.next[stone] = stone;
+        }
+    }
+
+    /// Recomputes all chains from the stones alone.
+    fn rebuild(&mut self) {
+        self.ko = None;
+        self.hash = 0;
+        for i in 0..self.len() {
+            if let Some(color) = self.stones[i] {
+                self.hash ^= zobrist::stone(i, color);
+            }
+            self.head[i] = i;
+            self.next[i] = i;
+            self.chain_len[i] = 1;
+            self.liberties[i] = 0;
+        }
+
+        for i in 0..self.len() {
+            for k in 0..self.neighbors[i].len() {
+                let n = self.neighbors[i][k];
+                if self.stones[i].is_some()
+                    && self.stones[n] == self.stones[i]
+                    && self.head[n] != self.head[i]
+                {
+                    self.merge(self.head[i], self.head[n]);
+                }
+            }
+        }
+
+        for i in 0..self.len() {
+            if self.stones[i].is_some() {
+                let empty = self.neighbors[i]
+                    .iter()
+                    .filter(|&&n| self.stones[n].is_none())
+                    .count();
+                self.liberties[self.head[i]] += empty;
+            }
+        }
+    }
+}
+
+impl Index<usize> for Board {
+    type Output = Option<Stone>;
+
+    fn index(&self, i: usize) -> &Self::Output {
+        &self.stones[i]
+    }
+}
+
+/// What is saved of a board: the chains and the hash follow from the stones. The neighbors follow from the
+/// topology, which is saved with the game.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedBoard {
+    size: BoardSize,
+    /// Row by row from the top left, `.` for an empty point, `X` for Black and `O` for White.
+    stones: String,
+    ko: Option<usize>,
+}
+
+#[cfg(feature = "serde")]
+impl From<Board> for SavedBoard {
+    fn from(board: Board) -> Self {
+        let stones = board
+            .stones
+            .iter()
+            .map(|stone| match stone {
+                None => '.',
+                Some(Stone::Black) => 'X',
+                Some(Stone::White) => 'O',
+            })
+            .collect();
+
+        Self {
+            size: board.size,
+            stones,
+            ko: board.ko,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedBoard> for Board {
+    type Error = String;
+
+    fn try_from(saved: SavedBoard) -> Result<Self, Self::Error> {
+        if saved.stones.len() != saved.size.len() {
+            return Err(format!(
+                "{} points on a board of {}",
+                saved.stones.len(),
+                saved.size
+            ));
+        }
+
+        let mut board = Board::new(saved.size);
+        for (i, c) in saved.stones.chars().enumerate() {
+            board.stones[i] = match c {
+                '.' => None,
+                'X' => Some(Stone::Black),
+                'O' => Some(Stone::White),
+                c => return Err(format!("invalid stone {:?}", c)),
+            };
+        }
+        board.rebuild();
+        if saved.ko.is_some_and(|i| i >= board.len()) {
+            return Err("ko is off the board".to_string());
+        }
+        board.ko = saved.ko;
+        Ok(board)
+    }
+}
+
+/// Boards are equal when their stones are; how the chains are linked does not matter.
+impl PartialEq for Board {
+    fn eq(&self, other: &Self) -> bool {
+        self.stones == other.stones
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::Diagram;
+    use crate::estimate;
+
+    /// Checks the chains, pseudo-liberties and hash kept up move by move against the ones recomputed from the stones.
+    fn check_chains(board: &Board) {
+        let mut rebuilt = board.clone();
+        rebuilt.rebuild();
+        assert_eq!(board.hash(), rebuilt.hash());
+        for i in 0..board.len() {
+            if board[i].is_none() {
+                continue;
+            }
+            let mut chain: Vec<usize> = board.chain(i).collect();
+            let mut expected: Vec<usize> = rebuilt.chain(i).collect();
+            chain.sort_unstable();
+            expected.sort_unstable();
+            assert_eq!(chain, expected, "chain of {}", i);
+            assert!(chain
+                .iter()
+                .all(|&stone| board.chain_head(stone) == board.chain_head(i)));
+            assert_eq!(board.chain_len(i), chain.len());
+            assert_eq!(board.pseudo_liberties(i), rebuilt.pseudo_liberties(i));
+        }
+    }
+
+    #[test]
+    fn random_games_keep_the_chains_right() {
+        let boards = [
+            (BoardSize::square(9), Topology::Grid),
+            (BoardSize::square(7), Topology::Torus),
+            (
+                BoardSize {
+                    width: 6,
+                    height: 5,
+                },
+                Topology::Cylinder,
+            ),
+            (
+                BoardSize {
+                    width: 2,
+                    height: 3,
+                },
+                Topology::Torus,
+            ),
+        ];
+        let mut rng = 0x2545_f491_4f6c_dd1d;
+        for (size, topology) in boards {
+            let mut board = Board::with_topology(size, &topology);
+            let mut color = Stone::Black;
+            for _ in 0..500 {
+                let empty: Vec<usize> = (0..board.len()).filter(|&i| board[i].is_none()).collect();
+                let i = empty[(estimate::random(&mut rng) % empty.len() as u64) as usize];
+                let (hash, captures, suicide) = (
+                    board.hash_after(i, color),
+                    board.captures(i, color),
+                    board.is_suicide(i, color),
+                );
+
+                let removed = board.play(i, color);
+                assert_eq!(board.hash(), hash, "{} {} at {}", topology, color, i);
+                assert_eq!(!removed.captured.is_empty(), captures);
+                assert_eq!(!removed.suicide.is_empty(), suicide);
+                check_chains(&board);
+                color = -color;
+            }
+        }
+    }
+
+    #[test]
+    fn ko_only_after_a_single_stone_captured_a_single_stone() {
+        let diagram: Diagram = "
+            . X O . .
+            X O . O .
+            . X O . .
+            . . . . .
+        "
+        .parse()
+        .unwrap();
+        let size = diagram.board.size();
+        let index = |x, y| Pos(x, y).index(size).unwrap();
+
+        let mut board = diagram.board.clone();
+        board.play(index(2, 1), Stone::Black);
+        assert_eq!(board.ko(), Some(index(1, 1)));
+        board.pass();
+        assert_eq!(board.ko(), None);
+
+        // the capturing stone has liberties besides the captured point
+        let mut board: Board = "
+            O X . .
+            . . . .
+        "
+        .parse::<Diagram>()
+        .unwrap()
+        .board;
+        assert_eq!(board.play(4, Stone::Black).captured, vec![0]);
+        assert_eq!(board.ko(), None);
+    }
+}
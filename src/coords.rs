@@ -0,0 +1,252 @@
+//! The ways of writing down a point on the board, like `D4` or `dd`.
+
+use crate::game::{BoardSize, Move, Pos};
+use std::fmt;
+
+/// Column letters as used by GTP, which skips `I`.
+pub const GTP_COLUMNS: &str = "ABCDEFGHJKLMNOPQRSTUVWXYZ";
+
+const SGF_COORDINATES: &str = "abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ";
+
+/// The kanji for 1 to 9.
+const KANJI_DIGITS: [char; 9] = ['一', '二', '三', '四', '五', '六', '七', '八', '九'];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Notation {
+    /// `D4`: a column letter, skipping `I`, and the row counted from 1 at the bottom. Up to 25 columns.
+    #[default]
+    Gtp,
+    /// `dd`: the column and then the row, lettered from `a` at the top left, continuing with `A` after `z`.
+    /// Up to 52 lines.
+    Sgf,
+    /// `16-四`: the column counted from 1 at the right, and the row in kanji counted from 1 at the top, as in
+    /// Japanese game records. Up to 99 rows.
+    Japanese,
+    /// `4-4`: the column counted from 1 at the left and the row counted from 1 at the bottom, like GTP but
+    /// without its limit on the number of columns.
+    Numeric,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CoordError {
+    /// The text is not a point in the notation at all.
+    Syntax(String),
+    /// The point is not on the board, or the notation cannot name it.
+    OffBoard(String),
+}
+
+impl fmt::Display for CoordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(s) => write!(f, "not a coordinate: {}", s),
+            Self::OffBoard(s) => write!(f, "{} is off the board", s),
+        }
+    }
+}
+
+impl std::error::Error for CoordError {}
+
+impl Notation {
+    pub const ALL: [Notation; 4] = [Self::Gtp, Self::Sgf, Self::Japanese, Self::Numeric];
+
+    /// Reads the point `s` on a board of `size`. Letters may be upper or lower case, except in SGF where
+    /// case matters.
+    pub fn parse(&self, s: &str, size: BoardSize) -> Result<Pos, CoordError> {
+        let syntax = || CoordError::Syntax(s.to_string());
+        let s = s.trim();
+
+        // each `None` if the number is before the first line
+        let (column, row) = match self {
+            Self::Gtp => {
+                let mut chars = s.chars();
+                let letter = chars.next().ok_or_else(syntax)?.to_ascii_uppercase();
+                let column = GTP_COLUMNS.find(letter).ok_or_else(syntax)?;
+                let row = parse_number(chars.as_str()).ok_or_else(syntax)?;
+                (Some(column), from_bottom(row, size))
+            }
+            Self::Sgf => {
+                let mut chars = s.chars();
+                let mut coordinate = || chars.next().and_then(|c| SGF_COORDINATES.find(c));
+                let (column, row) = (coordinate(), coordinate());
+                if column.is_none() || row.is_none() || chars.next().is_some() {
+                    return Err(syntax());
+                }
+                (column, row)
+            }
+            Self::Japanese => {
+                let digits = s.find(|c: char| !c.is_ascii_digit()).ok_or_else(syntax)?;
+                let column = parse_number(&s[..digits]).ok_or_else(syntax)?;
+                let row =
+                    parse_kanji(s[digits..].trim_start_matches(['-', 'の'])).ok_or_else(syntax)?;
+                (size.width.checked_sub(column), row.checked_sub(1))
+            }
+            Self::Numeric => {
+                let (column, row) = s.split_once('-').ok_or_else(syntax)?;
+                let column = parse_number(column).ok_or_else(syntax)?;
+                let row = parse_number(row).ok_or_else(syntax)?;
+                (column.checked_sub(1), from_bottom(row, size))
+            }
+        };
+
+        match (column, row) {
+            (Some(x), Some(y)) if x < size.width && y < size.height => Ok((x, y).into()),
+            _ => Err(CoordError::OffBoard(s.to_string())),
+        }
+    }
+
+    /// Writes the point `p` of a board of `size`.
+    pub fn format(&self, p: Pos, size: BoardSize) -> Result<String, CoordError> {
+        let off_board = || CoordError::OffBoard(format!("({}, {})", p.0, p.1));
+        if !p.valid(size) {
+            return Err(off_board());
+        }
+        let (x, y) = (p.0 as usize, p.1 as usize);
+
+        Ok(match self {
+            Self::Gtp => {
+                let column = GTP_COLUMNS.chars().nth(x).ok_or_else(off_board)?;
+                format!("{}{}", column, size.height - y)
+            }
+            Self::Sgf => {
+                let column = SGF_COORDINATES.chars().nth(x).ok_or_else(off_board)?;
+                let row = SGF_COORDINATES.chars().nth(y).ok_or_else(off_board)?;
+                format!("{}{}", column, row)
+            }
+            Self::Japanese => {
+                let row = kanji(y + 1).ok_or_else(off_board)?;
+                format!("{}-{}", size.width - x, row)
+            }
+            Self::Numeric => format!("{}-{}", x + 1, size.height - y),
+        })
+    }
+
+    /// Writes `mv`, with passes and resignations spelled out as GTP does.
+    pub fn format_move(&self, mv: Move, size: BoardSize) -> Result<String, CoordError> {
+        match mv {
+            Move::Place(p) => self.format(p, size),
+            Move::Pass => Ok("pass".to_string()),
+            Move::Resign => Ok("resign".to_string()),
+        }
+    }
+}
+
+fn parse_number(s: &str) -> Option<usize> {
+    if s.is_empty() || !s.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    s.parse().ok()
+}
+
+/// The index of the row numbered `row` from 1 at the bottom.
+fn from_bottom(row: usize, size: BoardSize) -> Option<usize> {
+    (1..=size.height).contains(&row).then(|| size.height - row)
+}
+
+/// Writes `n` in kanji, e.g. 十四 for 14, or `None` if it is not from 1 to 99.
+fn kanji(n: usize) -> Option<String> {
+    if !(1..=99).contains(&n) {
+        return None;
+    }
+    let (tens, ones) = (n / 10, n % 10);
+    let mut s = String::new();
+    if tens >= 2 {
+        s.push(KANJI_DIGITS[tens - 1]);
+    }
+    if tens >= 1 {
+        s.push('十');
+    }
+    if ones >= 1 {
+        s.push(KANJI_DIGITS[ones - 1]);
+    }
+    Some(s)
+}
+
+fn parse_kanji(s: &str) -> Option<usize> {
+    let digit = |c: char| KANJI_DIGITS.iter().position(|&k| k == c).map(|d| d + 1);
+    let chars: Vec<char> = s.chars().collect();
+
+    match chars.iter().position(|&c| c == '十') {
+        None => match chars[..] {
+            [c] => digit(c),
+            _ => None,
+        },
+        Some(k) => {
+            let tens = match chars[..k] {
+                [] => 1,
+                [c] => digit(c)?,
+                _ => return None,
+            };
+            let ones = match chars[k + 1..] {
+                [] => 0,
+                [c] => digit(c)?,
+                _ => return None,
+            };
+            Some(tens * 10 + ones)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_point_round_trips() {
+        for size in [
+            BoardSize::square(19),
+            BoardSize {
+                width: 13,
+                height: 7,
+            },
+        ] {
+            for notation in Notation::ALL {
+                for p in size.points() {
+                    let s = notation.format(p, size).unwrap();
+                    assert_eq!(notation.parse(&s, size), Ok(p), "{:?} {}", notation, s);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gtp_skips_i() {
+        let size = BoardSize::square(9);
+        assert_eq!(Notation::Gtp.parse("J9", size), Ok(Pos(8, 0)));
+        assert_eq!(Notation::Gtp.format(Pos(8, 0), size), Ok("J9".to_string()));
+        assert_eq!(Notation::Gtp.format(Pos(7, 8), size), Ok("H1".to_string()));
+        assert!(matches!(
+            Notation::Gtp.parse("I5", size),
+            Err(CoordError::Syntax(_))
+        ));
+    }
+
+    #[test]
+    fn points_off_the_board() {
+        let size = BoardSize::square(9);
+        assert!(matches!(
+            Notation::Gtp.parse("K1", size),
+            Err(CoordError::OffBoard(_))
+        ));
+        assert!(matches!(
+            Notation::Numeric.parse("1-10", size),
+            Err(CoordError::OffBoard(_))
+        ));
+        assert!(Notation::Gtp.format(Pos(9, 0), size).is_err());
+    }
+
+    #[test]
+    fn japanese_rows_go_up_to_99() {
+        let size = BoardSize {
+            width: 3,
+            height: 120,
+        };
+        assert_eq!(
+            Notation::Japanese.format(Pos(0, 98), size),
+            Ok("3-九十九".to_string())
+        );
+        assert!(matches!(
+            Notation::Japanese.format(Pos(0, 99), size),
+            Err(CoordError::OffBoard(_))
+        ));
+    }
+}
@@ -0,0 +1,287 @@
+//! Boards drawn in text, for tests, debugging and GTP's `showboard`:
+//!
+//! ```text
+//!    A B C D
+//!  4 . . . . 4
+//!  3 . X O . 3
+//!  2 . X(O). 2
+//!  1 . . . . 1
+//!    A B C D
+//! ```
+//!
+//! `X` is Black and `O` is White, and the last move is in parentheses. The coordinates are optional.
+
+use crate::board::Board;
+use crate::coords::GTP_COLUMNS;
+use crate::game::{BoardSize, Game, Move, Pos, Stone};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone)]
+pub struct Diagram {
+    pub board: Board,
+    pub last_move: Option<Pos>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DiagramError {
+    Empty,
+    /// A character that is neither a point nor a coordinate, on the line counting from 1.
+    InvalidChar {
+        line: usize,
+        c: char,
+    },
+    /// A row with a different number of points than the first.
+    RowLength {
+        line: usize,
+    },
+    /// A second last move, or one around an empty point.
+    LastMove {
+        line: usize,
+    },
+}
+
+impl fmt::Display for DiagramError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "diagram has no points"),
+            Self::InvalidChar { line, c } => write!(f, "unexpected {:?} on line {}", c, line),
+            Self::RowLength { line } => write!(f, "row on line {} has the wrong length", line),
+            Self::LastMove { line } => write!(f, "invalid last move on line {}", line),
+        }
+    }
+}
+
+impl std::error::Error for DiagramError {}
+
+impl Diagram {
+    /// The current position of `game`, with its last move.
+    pub fn from_game(game: &Game) -> Self {
+        let last_move = match game.tree().get(game.current()).mv {
+            Some((_, Move::Place(p))) => Some(p),
+            _ => None,
+        };
+        Self {
+            board: game.state.board.clone(),
+            last_move,
+        }
+    }
+
+    /// A game with the stones of the diagram set up, and the player who did not make the last move to move.
+    pub fn to_game(&self) -> Game {
+        let size = self.board.size();
+        let mut game = Game::new(size);
+        for (i, p) in size.points().enumerate() {
+            if self.board[i].is_some() {
+                game.setup(p, self.board[i]);
+            }
+        }
+        if let Some(color) = self.last_move.and_then(|p| game.stone_at(p)) {
+            game.set_turn(-color);
+        }
+        game
+    }
+}
+
+/// The game set up from `diagram`, which the tests of other modules write their positions in.
+#[cfg(test)]
+pub(crate) fn game(diagram: &str) -> Game {
+    diagram.parse::<Diagram>().unwrap().to_game()
+}
+
+/// Reads a diagram like the one at the top of this module. Lines with letters other than `X` and `O` are
+/// column headers, and the numbers around a row are taken to be its coordinates. `+` is an empty point too,
+/// as in diagrams that show the star points.
+impl FromStr for Diagram {
+    type Err = DiagramError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut rows: Vec<Vec<Option<Stone>>> = vec![];
+        let mut last_move = None;
+
+        for (line, text) in s.lines().enumerate() {
+            let line = line + 1;
+            let text = text.trim_matches(|c: char| c.is_ascii_digit() || c.is_whitespace());
+            let is_header = text
+                .chars()
+                .any(|c| c.is_alphabetic() && !"XxOo".contains(c));
+            if text.is_empty() || is_header {
+                continue;
+            }
+
+            let mut row = vec![];
+            let mut marked = false;
+            for c in text.chars() {
+                let point = match c {
+                    '.' | '+' => None,
+                    'X' | 'x' => Some(Stone::Black),
+                    'O' | 'o' => Some(Stone::White),
+                    '(' => {
+                        if last_move.is_some() || marked {
+                            return Err(DiagramError::LastMove { line });
+                        }
+                        marked = true;
+                        continue;
+                    }
+                    ')' | ' ' => continue,
+                    c => return Err(DiagramError::InvalidChar { line, c }),
+                };
+                if marked {
+                    if point.is_none() {
+                        return Err(DiagramError::LastMove { line });
+                    }
+                    last_move = Some((row.len(), rows.len()).into());
+                    marked = false;
+                }
+                row.push(point);
+            }
+
+            if marked {
+                return Err(DiagramError::LastMove { line });
+            }
+            if rows.first().is_some_and(|first| first.len() != row.len()) {
+                return Err(DiagramError::RowLength { line });
+            }
+            rows.push(row);
+        }
+
+        let size = BoardSize {
+            width: rows.first().map_or(0, Vec::len),
+            height: rows.len(),
+        };
+        if size.is_empty() {
+            return Err(DiagramError::Empty);
+        }
+
+        let mut board = Board::new(size);
+        for (i, stone) in rows.into_iter().flatten().enumerate() {
+            if stone.is_some() {
+                board.set(i, stone);
+            }
+        }
+        Ok(Self { board, last_move })
+    }
+}
+
+/// Draws the diagram, with coordinates in the alternate form `{:#}`.
+impl fmt::Display for Diagram {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_diagram(f, &self.board, self.last_move)
+    }
+}
+
+/// Draws the board as a diagram, with coordinates in the alternate form `{:#}`.
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_diagram(f, self, None)
+    }
+}
+
+fn write_diagram(f: &mut fmt::Formatter<'_>, board: &Board, last_move: Option<Pos>) -> fmt::Result {
+    let BoardSize { width, height } = board.size();
+    let coordinates = f.alternate();
+    // GTP has no letters for the columns of the widest boards
+    let header = GTP_COLUMNS
+        .get(..width)
+        .filter(|_| coordinates)
+        .map(|columns| {
+            columns
+                .chars()
+                .map(|c| format!(" {}", c))
+                .collect::<String>()
+        });
+
+    if let Some(header) = &header {
+        writeln!(f, "  {}", header)?;
+    }
+    for y in 0..height {
+        if coordinates {
+            write!(f, "{:2}", height - y)?;
+        }
+        let mut marked = false;
+        for x in 0..width {
+            let p = Pos::from((x, y));
+            // the parentheses take the place of the spaces around the last move
+            let separator = if Some(p) == last_move {
+                "("
+            } else if marked {
+                ")"
+            } else if x > 0 || coordinates {
+                " "
+            } else {
+                ""
+            };
+            marked = Some(p) == last_move;
+
+            let point = match board[p.index(board.size()).unwrap()] {
+                Some(Stone::Black) => 'X',
+                Some(Stone::White) => 'O',
+                None => '.',
+            };
+            write!(f, "{}{}", separator, point)?;
+        }
+        if marked {
+            write!(f, ")")?;
+        }
+        if coordinates {
+            write!(f, " {}", height - y)?;
+        }
+        writeln!(f)?;
+    }
+    if let Some(header) = &header {
+        writeln!(f, "  {}", header)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLAIN: &str = "\
+. . . .
+. X O .
+. X(O).
+. . . .
+";
+
+    const WITH_COORDINATES: &str = "   A B C D
+ 4 . . . . 4
+ 3 . X O . 3
+ 2 . X(O). 2
+ 1 . . . . 1
+   A B C D
+";
+
+    #[test]
+    fn round_trips_with_the_last_move() {
+        let diagram: Diagram = PLAIN.parse().unwrap();
+        assert_eq!(diagram.last_move, Some(Pos(2, 2)));
+        assert_eq!(diagram.to_string(), PLAIN);
+        assert_eq!(format!("{:#}", diagram), WITH_COORDINATES);
+
+        let diagram: Diagram = WITH_COORDINATES.parse().unwrap();
+        assert_eq!(diagram.to_string(), PLAIN);
+    }
+
+    #[test]
+    fn the_other_player_moves_after_the_last_move() {
+        let game = game(PLAIN);
+        assert_eq!(game.turn, Stone::Black);
+        assert_eq!(game.stone_at(Pos(1, 1)), Some(Stone::Black));
+        assert!(Diagram::from_game(&game).board == game.state.board);
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!("".parse::<Diagram>().err(), Some(DiagramError::Empty));
+        assert_eq!(
+            ". .\n. . .\n".parse::<Diagram>().err(),
+            Some(DiagramError::RowLength { line: 2 })
+        );
+        assert_eq!(
+            ". (.)\n".parse::<Diagram>().err(),
+            Some(DiagramError::LastMove { line: 1 })
+        );
+    }
+}
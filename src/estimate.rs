@@ -0,0 +1,341 @@
+//! Guesses which stones are dead and who owns which point, by playing the game out at random many times and
+//! counting who ended up with each point. What Benson's algorithm proves needs no guessing.
+
+use crate::benson;
+use crate::board::Board;
+use crate::game::{BoardSize, Game, GameState, Pos, Stone};
+use crate::scoring::{self, Score};
+use enum_map::EnumMap;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Alive,
+    Dead,
+    /// Alive only because neither side can approach the other's liberties without putting itself in atari.
+    Seki,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Estimate {
+    size: BoardSize,
+    /// From 1 where Black surely ends up with the point to -1 where White does, indexed like `GameState::board`.
+    pub ownership: Vec<f64>,
+    /// The status of the chain of every stone, indexed like `GameState::board`; `None` for empty points.
+    pub status: Vec<Option<Status>>,
+}
+
+impl Estimate {
+    /// Who is likely to end up with point `p`, if anyone is.
+    pub fn owner(&self, p: Pos) -> Option<Stone> {
+        let ownership = self.ownership[p.index(self.size)?];
+        if ownership > 0.5 {
+            Some(Stone::Black)
+        } else if ownership < -0.5 {
+            Some(Stone::White)
+        } else {
+            None
+        }
+    }
+
+    pub fn dead(&self) -> HashSet<Pos> {
+        (0..self.status.len())
+            .filter(|&i| self.status[i] == Some(Status::Dead))
+            .map(|i| self.size.pos(i))
+            .collect()
+    }
+
+    /// The score of the current position of `game`, counting the stones this estimate deems dead as captured.
+    pub fn score(&self, game: &Game) -> Score {
+        scoring::score(game, &self.dead())
+    }
+}
+
+pub(crate) fn sign(color: Stone) -> f64 {
+    match color {
+        Stone::Black => 1.0,
+        Stone::White => -1.0,
+    }
+}
+
+/// Estimates the position in `state` with `turn` to move from `playouts` random games. The same position always
+/// gives the same estimate.
+pub fn estimate(state: &GameState, turn: Stone, playouts: usize) -> Estimate {
+    let board = &state.board;
+    let mut rng = state.hash(turn) | 1;
+
+    let mut ownership = vec![0.0; board.len()];
+    for _ in 0..playouts {
+        let mut end = board.clone();
+        playout(&mut end, turn, &mut rng);
+        for (i, ownership) in ownership.iter_mut().enumerate() {
+            if let Some(owner) = area_owner(&end, i) {
+                *ownership += sign(owner) / playouts as f64;
+            }
+        }
+    }
+
+    let mut pass_alive = HashSet::new();
+    for (color, alive) in benson::pass_alive(state) {
+        for p in alive.chains.iter().chain(&alive.regions).flatten() {
+            ownership[p.index(board.size()).unwrap()] = sign(color);
+        }
+        for chain in &alive.chains {
+            pass_alive.insert(board.chain_head(chain[0].index(board.size()).unwrap()));
+        }
+    }
+
+    let mut status = vec![None; board.len()];
+    for i in 0..board.len() {
+        if let Some(color) = board[i] {
+            let head = board.chain_head(i);
+            if status[head].is_none() {
+                let own: f64 = board.chain(head).map(|j| ownership[j] * sign(color)).sum();
+                status[head] = Some(if own < 0.0 {
+                    Status::Dead
+                } else {
+                    Status::Alive
+                });
+            }
+        }
+    }
+
+    // living chains of both colors sharing a liberty that nobody gets are in seki when it is one of their last two,
+    // as whoever fills it puts themselves in atari; chains that are alive no matter what never are
+    for i in 0..board.len() {
+        if board[i].is_some() || ownership[i].abs() >= 0.5 {
+            continue;
+        }
+        let stuck: Vec<usize> = board
+            .neighbors(i)
+            .iter()
+            .filter(|&&n| board[n].is_some())
+            .map(|&n| board.chain_head(n))
+            .filter(|&head| {
+                status[head] != Some(Status::Dead)
+                    && !pass_alive.contains(&head)
+                    && board.liberties(head).len() <= 2
+            })
+            .collect();
+        let both = stuck.iter().any(|&h| board[h] == Some(Stone::Black))
+            && stuck.iter().any(|&h| board[h] == Some(Stone::White));
+        if both {
+            for head in stuck {
+                status[head] = Some(Status::Seki);
+            }
+        }
+    }
+
+    for i in 0..board.len() {
+        if board[i].is_some() {
+            status[i] = status[board.chain_head(i)];
+        }
+    }
+
+    Estimate {
+        size: board.size(),
+        ownership,
+        status,
+    }
+}
+
+/// Who has point `i` at the end of a playout: the color of the stone on it, or of all the stones around it.
+pub(crate) fn area_owner(board: &Board, i: usize) -> Option<Stone> {
+    if board[i].is_some() {
+        return board[i];
+    }
+    let color = board[*board.neighbors(i).first()?]?;
+    if board.neighbors(i).iter().all(|&n| board[n] == Some(color)) {
+        Some(color)
+    } else {
+        None
+    }
+}
+
+/// Who has each point of `board` when the game ends as it stands: the color of the stone on it, or of all the
+/// stones around the empty region it is in.
+pub(crate) fn area_owners(board: &Board) -> Vec<Option<Stone>> {
+    let mut owners: Vec<Option<Stone>> = board.iter().copied().collect();
+    let mut visited = vec![false; board.len()];
+
+    for start in 0..board.len() {
+        if board[start].is_some() || visited[start] {
+            continue;
+        }
+
+        let mut region = vec![];
+        let mut borders: EnumMap<Stone, bool> = EnumMap::default();
+        let mut todo = vec![start];
+        visited[start] = true;
+        while let Some(i) = todo.pop() {
+            region.push(i);
+            for &n in board.neighbors(i) {
+                match board[n] {
+                    Some(color) => borders[color] = true,
+                    None if !visited[n] => {
+                        visited[n] = true;
+                        todo.push(n);
+                    }
+                    None => {}
+                }
+            }
+        }
+
+        let owner = match (borders[Stone::Black], borders[Stone::White]) {
+            (true, false) => Some(Stone::Black),
+            (false, true) => Some(Stone::White),
+            _ => None,
+        };
+        for i in region {
+            owners[i] = owner;
+        }
+    }
+
+    owners
+}
+
+pub(crate) fn random(rng: &mut u64) -> u64 {
+    *rng ^= *rng << 13;
+    *rng ^= *rng >> 7;
+    *rng ^= *rng << 17;
+    *rng
+}
+
+/// Plays random moves until both players pass, which they do when nothing but filling their own eyes is left.
+/// Returns the stones played, in order.
+pub(crate) fn playout(board: &mut Board, mut turn: Stone, rng: &mut u64) -> Vec<(usize, Stone)> {
+    let mut empty: Vec<usize> = (0..board.len()).filter(|&i| board[i].is_none()).collect();
+    let mut played = vec![];
+    let mut passes = 0;
+
+    for _ in 0..3 * board.len() {
+        if passes >= 2 {
+            break;
+        }
+
+        let start = if empty.is_empty() {
+            0
+        } else {
+            random(rng) as usize % empty.len()
+        };
+        let candidate = (0..empty.len())
+            .map(|k| (start + k) % empty.len())
+            .find(|&k| is_reasonable(board, empty[k], turn));
+
+        match candidate {
+            Some(k) => {
+                let i = empty.swap_remove(k);
+                empty.extend(board.play(i, turn).captured);
+                played.push((i, turn));
+                passes = 0;
+            }
+            None => {
+                board.pass();
+                passes += 1;
+            }
+        }
+        turn = -turn;
+    }
+    played
+}
+
+/// Whether a random player should consider playing at the empty point `i`: not into a ko, suicide or
+/// its own eye, and not putting a chain of more than one stone into atari.
+pub(crate) fn is_reasonable(board: &Board, i: usize, color: Stone) -> bool {
+    board.ko() != Some(i)
+        && !board.neighbors(i).iter().all(|&n| board[n] == Some(color))
+        && !board.is_suicide(i, color)
+        && !is_self_atari(board, i, color)
+}
+
+fn is_self_atari(board: &Board, i: usize, color: Stone) -> bool {
+    if board.captures(i, color) {
+        return false;
+    }
+
+    let mut liberties = vec![];
+    let mut stones = 1;
+    let add = |liberties: &mut Vec<usize>, n: usize| {
+        if n != i && board[n].is_none() && !liberties.contains(&n) {
+            liberties.push(n);
+        }
+    };
+
+    for &n in board.neighbors(i) {
+        add(&mut liberties, n);
+    }
+    if liberties.len() >= 2 {
+        return false;
+    }
+
+    let mut heads: Vec<usize> = vec![];
+    for &n in board.neighbors(i) {
+        if board[n] != Some(color) || heads.contains(&board.chain_head(n)) {
+            continue;
+        }
+        heads.push(board.chain_head(n));
+        for stone in board.chain(n) {
+            stones += 1;
+            for &m in board.neighbors(stone) {
+                add(&mut liberties, m);
+            }
+            if liberties.len() >= 2 {
+                return false;
+            }
+        }
+    }
+
+    stones > 1 && liberties.len() < 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::game;
+
+    fn status(estimate: &Estimate, p: Pos) -> Option<Status> {
+        estimate.status[p.index(estimate.size).unwrap()]
+    }
+
+    #[test]
+    fn walls_across_dame_are_alive_and_stones_inside_them_dead() {
+        let game = game(
+            "
+            . O . X . O .
+            X X X X . O O
+            . X . X . O .
+            X X X X . O O
+            X X X X . O O
+            X X X X . O O
+            X X X X . O O
+            ",
+        );
+        let estimate = estimate(&game.state, game.turn, 100);
+
+        assert_eq!(status(&estimate, Pos(3, 3)), Some(Status::Alive));
+        assert_eq!(status(&estimate, Pos(5, 3)), Some(Status::Alive));
+        assert_eq!(status(&estimate, Pos(1, 0)), Some(Status::Dead));
+        assert_eq!(estimate.dead(), HashSet::from([Pos(1, 0)]));
+        assert_eq!(estimate.owner(Pos(1, 0)), Some(Stone::Black));
+        for y in 0..7 {
+            assert_eq!(estimate.owner(Pos(4, y)), None);
+            assert_eq!(status(&estimate, Pos(4, y)), None);
+        }
+    }
+
+    #[test]
+    fn chains_that_cannot_fill_their_shared_liberty_are_in_seki() {
+        let game = game(
+            "
+            . X X . O O .
+            X X X X O O O
+            ",
+        );
+        let estimate = estimate(&game.state, game.turn, 100);
+
+        assert_eq!(status(&estimate, Pos(1, 0)), Some(Status::Seki));
+        assert_eq!(status(&estimate, Pos(4, 0)), Some(Status::Seki));
+        assert_eq!(estimate.owner(Pos(3, 0)), None);
+        assert!(estimate.dead().is_empty());
+    }
+}
@@ -1,29 +1,31 @@
-use druid::{Data, Lens};
+use crate::board::Board;
+use crate::handicap::{self, Placement};
+use crate::info::GameInfo;
+use crate::rules::{Ruleset, Superko};
+use crate::topology::Topology;
+use crate::tree::{GameTree, NodeId};
+use crate::zobrist;
 use enum_map::{Enum, EnumMap};
+use std::collections::HashSet;
+use std::fmt;
 use std::ops::Neg;
 
-#[derive(Clone, Debug, Data, Copy, PartialEq, Eq)]
+#[derive(Clone, Debug, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Pos(pub i32, pub i32);
 
 impl Pos {
-    pub fn neighbors(&self, size: usize) -> Vec<Pos> {
-        vec![
-            Pos(self.0 - 1, self.1),
-            Pos(self.0 + 1, self.1),
-            Pos(self.0, self.1 - 1),
-            Pos(self.0, self.1 + 1),
-        ]
-        .iter()
-        .filter(|&p| p.0 >= 0 && p.0 < (size as i32) && p.1 >= 0 && p.1 < (size as i32))
-        .map(|p| *p)
-        .collect()
+    /// The points next to this one, which depend on how the edges of the board are connected.
+    pub fn neighbors(&self, size: BoardSize, topology: &Topology) -> impl Iterator<Item = Pos> {
+        topology.neighbors(*self, size).into_iter()
     }
 
-    pub fn valid(&self, size: usize) -> bool {
-        self.0 >= 0 && self.0 < (size as i32) && self.1 >= 0 && self.1 < (size as i32)
+    pub fn valid(&self, size: BoardSize) -> bool {
+        self.0 >= 0 && self.0 < (size.width as i32) && self.1 >= 0 && self.1 < (size.height as i32)
     }
 
-    pub fn and_valid(&self, size: usize) -> Option<Pos> {
+    pub fn and_valid(&self, size: BoardSize) -> Option<Pos> {
         if self.valid(size) {
             Some(*self)
         } else {
@@ -31,9 +33,9 @@ impl Pos {
         }
     }
 
-    pub fn index(&self, size: usize) -> Option<usize> {
+    pub fn index(&self, size: BoardSize) -> Option<usize> {
         if self.valid(size) {
-            Some((self.1 as usize) * size + (self.0 as usize))
+            Some((self.1 as usize) * size.width + (self.0 as usize))
         } else {
             None
         }
@@ -46,7 +48,75 @@ impl From<(usize, usize)> for Pos {
     }
 }
 
-#[derive(Clone, PartialEq, Copy, Enum, Data)]
+/// The number of columns and rows of a board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct BoardSize {
+    pub width: usize,
+    pub height: usize,
+}
+
+impl BoardSize {
+    pub fn square(size: usize) -> Self {
+        Self {
+            width: size,
+            height: size,
+        }
+    }
+
+    pub fn is_square(&self) -> bool {
+        self.width == self.height
+    }
+
+    /// Number of points.
+    pub fn len(&self) -> usize {
+        self.width * self.height
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The point with index `i`, counting row by row from the top left.
+    pub fn pos(&self, i: usize) -> Pos {
+        (i % self.width, i / self.width).into()
+    }
+
+    /// All points, in index order.
+    pub fn points(&self) -> impl Iterator<Item = Pos> {
+        let size = *self;
+        (0..size.len()).map(move |i| size.pos(i))
+    }
+}
+
+impl From<usize> for BoardSize {
+    fn from(size: usize) -> Self {
+        Self::square(size)
+    }
+}
+
+impl From<(usize, usize)> for BoardSize {
+    fn from((width, height): (usize, usize)) -> Self {
+        Self { width, height }
+    }
+}
+
+/// Formats the size like `19` for square boards, and like `9x13` otherwise.
+impl fmt::Display for BoardSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_square() {
+            write!(f, "{}", self.width)
+        } else {
+            write!(f, "{}x{}", self.width, self.height)
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Copy, Enum)]
+#[cfg_attr(feature = "druid", derive(druid::Data))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
 pub enum Stone {
     White,
     Black,
@@ -63,118 +133,797 @@ impl Neg for Stone {
     }
 }
 
-type Board = Vec<Option<Stone>>;
+impl fmt::Display for Stone {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::White => write!(f, "W"),
+            Self::Black => write!(f, "B"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Move {
+    Place(Pos),
+    Pass,
+    Resign,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GameResult {
+    Resignation { winner: Stone },
+    Score { winner: Stone, margin: f64 },
+    Draw,
+}
+
+impl GameResult {
+    pub fn winner(&self) -> Option<Stone> {
+        match *self {
+            Self::Resignation { winner } | Self::Score { winner, .. } => Some(winner),
+            Self::Draw => None,
+        }
+    }
+}
 
-#[derive(Clone, PartialEq, Data, Lens)]
+/// Formats the result the way SGF's `RE` property does, e.g. `B+R` or `W+6.5`.
+impl fmt::Display for GameResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Resignation { winner } => write!(f, "{}+R", winner),
+            Self::Score { winner, margin } => write!(f, "{}+{}", winner, margin),
+            Self::Draw => write!(f, "0"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum GameStatus {
+    Playing,
+    /// Both players passed in a row; dead stones are being agreed upon.
+    Scoring,
+    Finished(GameResult),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum IllegalMove {
+    Occupied,
+    OutOfBounds,
+    /// The move would retake a ko right away, or repeat an earlier position when superko applies.
+    Ko,
+    /// The move would leave its own group without liberties.
+    Suicide,
+    /// The game is being scored or has finished.
+    GameOver,
+    /// Black has to place at least two handicap stones before moving on to the game.
+    HandicapNotPlaced,
+}
+
+impl fmt::Display for IllegalMove {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Occupied => write!(f, "point is occupied"),
+            Self::OutOfBounds => write!(f, "point is off the board"),
+            Self::Ko => write!(f, "ko cannot be retaken yet"),
+            Self::Suicide => write!(f, "suicide"),
+            Self::GameOver => write!(f, "game is over"),
+            Self::HandicapNotPlaced => write!(f, "handicap stones have to be placed first"),
+        }
+    }
+}
+
+impl std::error::Error for IllegalMove {}
+
+/// What happened on the board when a move was played.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MoveOutcome {
+    /// Opponent stones taken off the board.
+    pub captured: Vec<Pos>,
+    /// Own stones taken off the board because the move was a suicide.
+    pub suicide: Vec<Pos>,
+}
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data, druid::Lens))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GameState {
-    #[data(eq)]
+    #[cfg_attr(feature = "druid", data(eq))]
     pub board: Board,
-    #[data(eq)]
+    #[cfg_attr(feature = "druid", data(eq))]
     pub captures: EnumMap<Stone, usize>,
 }
 
 impl GameState {
-    pub fn new(size: usize) -> Self {
+    pub fn new(size: BoardSize) -> Self {
+        Self::with_topology(size, &Topology::Grid)
+    }
+
+    pub fn with_topology(size: BoardSize, topology: &Topology) -> Self {
         Self {
-            board: vec![None; size * size],
+            board: Board::with_topology(size, topology),
             captures: enum_map! {
                 Stone::White => 0,
                 Stone::Black => 0,
             },
         }
     }
+
+    /// The Zobrist hash of the position with `turn` to move, e.g. as a key for transposition tables.
+    pub fn hash(&self, turn: Stone) -> u64 {
+        self.board.hash() ^ zobrist::turn(turn)
+    }
 }
 
-#[derive(Clone, Data, Lens)]
+/// The version of the JSON format of saved games, see `schema/game.v1.json`.
+#[cfg(feature = "serde")]
+pub const SCHEMA_VERSION: u32 = 1;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "druid", derive(druid::Data, druid::Lens))]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SavedGame", try_from = "SavedGame")
+)]
 pub struct Game {
-    pub size: usize,
+    pub size: BoardSize,
+    /// How the edges of the board connect; fixed when the game is created.
+    #[cfg_attr(feature = "druid", data(eq), lens(ignore))]
+    topology: Topology,
     pub turn: Stone,
     pub state: GameState,
-    #[data(eq)]
-    history: Vec<GameState>,
+    #[cfg_attr(feature = "druid", data(eq))]
+    pub ruleset: Ruleset,
+    pub info: GameInfo,
+    /// Handicap stones Black still has to place, when they are placed freely.
+    #[cfg_attr(feature = "druid", lens(ignore))]
+    handicap_to_place: usize,
+    #[cfg_attr(feature = "druid", data(eq))]
+    pub status: GameStatus,
+    #[cfg_attr(feature = "druid", data(eq), lens(ignore))]
+    tree: GameTree,
+    /// The node of `tree` that `state` and `turn` belong to.
+    #[cfg_attr(feature = "druid", lens(ignore))]
+    current: NodeId,
+    /// The hashes of the positions on the path to `current`, with the player to move, for superko.
+    #[cfg_attr(feature = "druid", data(ignore), lens(ignore))]
+    positions: HashSet<u64>,
+}
+
+/// What is saved of a game: the current position, the player to move and the positions for superko follow from
+/// the tree.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SavedGame {
+    version: u32,
+    size: BoardSize,
+    topology: Topology,
+    ruleset: Ruleset,
+    info: GameInfo,
+    handicap_to_place: usize,
+    status: GameStatus,
+    tree: GameTree,
+    current: NodeId,
+}
+
+#[cfg(feature = "serde")]
+impl From<Game> for SavedGame {
+    fn from(game: Game) -> Self {
+        Self {
+            version: SCHEMA_VERSION,
+            size: game.size,
+            topology: game.topology,
+            ruleset: game.ruleset,
+            info: game.info,
+            handicap_to_place: game.handicap_to_place,
+            status: game.status,
+            tree: game.tree,
+            current: game.current,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl TryFrom<SavedGame> for Game {
+    type Error = String;
+
+    fn try_from(mut saved: SavedGame) -> Result<Self, Self::Error> {
+        if saved.version != SCHEMA_VERSION {
+            return Err(format!("unsupported version {}", saved.version));
+        }
+        if !saved.tree.is_consistent() || saved.current >= saved.tree.len() {
+            return Err("inconsistent game tree".to_string());
+        }
+        if !saved.topology.fits(saved.size) {
+            return Err("graph does not fit the board".to_string());
+        }
+        if saved.handicap_to_place > saved.info.handicap {
+            return Err("more handicap stones to place than the handicap".to_string());
+        }
+
+        for id in 0..saved.tree.len() {
+            match saved.tree.get(id).mv {
+                Some((_, Move::Place(p))) if !p.valid(saved.size) => {
+                    return Err(format!(
+                        "move at [{}, {}] off the board of {}",
+                        p.0, p.1, saved.size
+                    ));
+                }
+                // resigning ends the game without a node of its own
+                Some((_, Move::Resign)) => return Err("resignation in the game tree".to_string()),
+                _ => {}
+            }
+            let board = &mut saved.tree.get_mut(id).state.board;
+            if board.size() != saved.size {
+                return Err(format!(
+                    "board of {} in a game of {}",
+                    board.size(),
+                    saved.size
+                ));
+            }
+            if saved.topology != Topology::Grid {
+                board.set_topology(&saved.topology);
+            }
+        }
+
+        let node = saved.tree.get(saved.current);
+        let mut game = Self {
+            size: saved.size,
+            topology: saved.topology,
+            turn: node.turn,
+            state: node.state.clone(),
+            ruleset: saved.ruleset,
+            info: saved.info,
+            handicap_to_place: saved.handicap_to_place,
+            status: saved.status,
+            tree: saved.tree,
+            current: saved.current,
+            positions: HashSet::new(),
+        };
+        game.collect_positions();
+        Ok(game)
+    }
 }
 
 impl Game {
-    pub fn new(size: usize) -> Self {
+    /// A game on a board of `size`, which is either a single number for square boards or `(width, height)`.
+    pub fn new(size: impl Into<BoardSize>) -> Self {
+        Self::with_ruleset(size, Ruleset::default())
+    }
+
+    pub fn with_ruleset(size: impl Into<BoardSize>, ruleset: Ruleset) -> Self {
+        Self::with_topology(size, ruleset, Topology::Grid)
+    }
+
+    /// A game on a board whose points are connected by `topology`.
+    ///
+    /// # Panics
+    ///
+    /// If `topology` is a graph that does not fit `size`, see `Topology::fits`.
+    pub fn with_topology(size: impl Into<BoardSize>, ruleset: Ruleset, topology: Topology) -> Self {
+        let size = size.into();
+        let turn = ruleset.first_player();
+        let state = GameState::with_topology(size, &topology);
         Self {
             size,
-            turn: Stone::White,
-            state: GameState::new(size),
-            history: vec![],
+            topology,
+            turn,
+            positions: HashSet::from([state.hash(turn)]),
+            state: state.clone(),
+            ruleset,
+            info: GameInfo::default(),
+            handicap_to_place: 0,
+            status: GameStatus::Playing,
+            tree: GameTree::new(state, turn),
+            current: GameTree::ROOT,
         }
     }
 
-    /// Checks whether the structure around position `p` is surrounded, and if so, returns the the whole structure.
-    pub fn is_surrounded(&self, p: Pos) -> Option<(Stone, Vec<Pos>)> {
-        let mut structure: Vec<Pos> = vec![];
-
-        if let Some(color) = self.stone_at(p) {
-            let mut todo: Vec<Pos> = vec![p];
-
-            while let Some(p) = todo.pop() {
-                structure.push(p);
-
-                for np in p.neighbors(self.size) {
-                    if !self.has_stone_at(np) {
-                        return None;
-                    } else if let Some(neighbor_color) = self.stone_at(np) {
-                        if neighbor_color == color
-                            && !structure.contains(&np)
-                            && !todo.contains(&np)
-                        {
-                            todo.push(np);
-                        }
-                    }
-                }
+    /// Plays `mv` for the side to move. If the move was played here before, its node is reused, otherwise it starts a new
+    /// variation, for which the recorded result no longer holds.
+    pub fn play(&mut self, mv: Move) -> Result<MoveOutcome, IllegalMove> {
+        if self.status != GameStatus::Playing {
+            return Err(IllegalMove::GameOver);
+        }
+        if self.handicap_to_place > 0 {
+            if let Move::Place(p) = mv {
+                return self.place_handicap_stone(p);
             }
+            // any other move ends the placement early, with the stones placed so far, of which a handicap needs two
+            if self.handicap_placed() < 2 {
+                return Err(IllegalMove::HandicapNotPlaced);
+            }
+            self.info.handicap -= self.handicap_to_place;
+            self.handicap_to_place = 0;
+        }
+
+        let outcome = match mv {
+            Move::Place(p) => self.place_stone(p)?,
+            Move::Pass => {
+                self.state.board.pass();
+                MoveOutcome::default()
+            }
+            Move::Resign => {
+                self.tree.get_mut(self.current).resigned = true;
+                self.update_status();
+                return Ok(MoveOutcome::default());
+            }
+        };
 
-            return Some((color, structure));
+        let color = self.turn;
+        self.turn = -self.turn;
+        self.current = match self.tree.find_child(self.current, color, mv) {
+            Some(child) => child,
+            None => {
+                // the game no longer is the one its result was recorded for
+                self.info.result.clear();
+                self.tree
+                    .add_child(self.current, color, mv, self.state.clone(), self.turn)
+            }
+        };
+        self.tree.select(self.current);
+        self.positions.insert(self.hash());
+        self.update_status();
+
+        Ok(outcome)
+    }
+
+    /// Changes whose turn it is in the current position, e.g. to let one player move twice when replaying a record.
+    pub fn set_turn(&mut self, color: Stone) {
+        self.turn = color;
+        self.tree.get_mut(self.current).turn = color;
+        self.collect_positions();
+    }
+
+    /// Puts a stone on the board without it being a move, like SGF's `AB` and `AW`. Only possible before the first move;
+    /// returns whether it was.
+    pub fn setup(&mut self, p: Pos, color: Option<Stone>) -> bool {
+        if self.current != GameTree::ROOT || !self.tree.get(GameTree::ROOT).children.is_empty() {
+            return false;
         }
 
-        // should not happen
-        None
+        match p.index(self.size) {
+            Some(i) => {
+                self.state.board.set(i, color);
+                self.tree.get_mut(GameTree::ROOT).state.board.set(i, color);
+                self.collect_positions();
+                true
+            }
+            None => false,
+        }
     }
 
-    fn remove_if_surrounded(&mut self, p: Pos) {
-        if let Some((color, structure)) = self.is_surrounded(p) {
-            let num_captures = structure.len();
-            for p in structure {
-                if let Some(i) = p.index(self.size) {
-                    self.state.board[i] = None;
+    /// Gives Black `stones` handicap stones, after which White moves first and komi is 0.5. With free placement, Black
+    /// places them by playing them, and may stop early by passing once two are down. Only possible on an empty board
+    /// without any moves, not even ones that were taken back, and fixed placement only for as many stones as there are
+    /// star points. Returns whether the handicap was given.
+    pub fn set_handicap(&mut self, stones: usize, placement: Placement) -> bool {
+        let root = self.tree.get(GameTree::ROOT);
+        if !root.children.is_empty() || root.state.board.iter().any(Option::is_some) {
+            return false;
+        }
+        let points = match placement {
+            Placement::Fixed => match handicap::fixed_points(self.size, stones) {
+                Some(points) => points,
+                None => return false,
+            },
+            Placement::Free if stones < 2 || stones >= self.state.board.len() => return false,
+            Placement::Free => vec![],
+        };
+
+        match placement {
+            Placement::Fixed => {
+                for p in points {
+                    self.setup(p, Some(Stone::Black));
                 }
+                self.set_turn(Stone::White);
+            }
+            Placement::Free => {
+                self.handicap_to_place = stones;
+                self.set_turn(Stone::Black);
             }
-            self.state.captures[color] += num_captures;
         }
+
+        self.info.handicap = stones;
+        self.info.komi = 0.5;
+        true
+    }
+
+    /// Handicap stones Black still has to place, before White's first move.
+    pub fn handicap_to_place(&self) -> usize {
+        self.handicap_to_place
+    }
+
+    fn handicap_placed(&self) -> usize {
+        self.info.handicap - self.handicap_to_place
+    }
+
+    fn place_handicap_stone(&mut self, p: Pos) -> Result<MoveOutcome, IllegalMove> {
+        self.check_handicap_stone(p)?;
+        self.setup(p, Some(Stone::Black));
+        self.handicap_to_place -= 1;
+        if self.handicap_to_place == 0 {
+            self.set_turn(Stone::White);
+        }
+        Ok(MoveOutcome::default())
     }
 
-    pub fn try_place_stone(&mut self, p: Pos) {
-        if let Some(i) = p.index(self.size) {
-            if self.has_stone_at(p) {
-                return;
+    /// Handicap stones may go on any empty point.
+    fn check_handicap_stone(&self, p: Pos) -> Result<(), IllegalMove> {
+        if !p.valid(self.size) {
+            return Err(IllegalMove::OutOfBounds);
+        }
+        if self.has_stone_at(p) {
+            return Err(IllegalMove::Occupied);
+        }
+        Ok(())
+    }
+
+    /// The Zobrist hash of the current position and the player to move.
+    pub fn hash(&self) -> u64 {
+        self.state.hash(self.turn)
+    }
+
+    /// Recomputes `positions` after moving through the tree.
+    fn collect_positions(&mut self) {
+        let tree = &self.tree;
+        self.positions = tree
+            .ancestors(self.current)
+            .map(|id| tree.get(id))
+            .map(|node| node.state.hash(node.turn))
+            .collect();
+    }
+
+    pub fn topology(&self) -> &Topology {
+        &self.topology
+    }
+
+    pub fn tree(&self) -> &GameTree {
+        &self.tree
+    }
+
+    pub fn current(&self) -> NodeId {
+        self.current
+    }
+
+    /// The position before the first move, including any setup stones.
+    pub fn initial_state(&self) -> &GameState {
+        &self.tree.get(GameTree::ROOT).state
+    }
+
+    /// The player to move before the first move.
+    pub fn first_turn(&self) -> Stone {
+        self.tree.get(GameTree::ROOT).turn
+    }
+
+    /// The moves that led to the current position, with the color that played them.
+    pub fn moves(&self) -> impl Iterator<Item = (Stone, Move)> + '_ {
+        self.tree
+            .path(self.current)
+            .into_iter()
+            .filter_map(|id| self.tree.get(id).mv)
+    }
+
+    /// Number of moves played to reach the current position.
+    pub fn move_number(&self) -> usize {
+        self.tree.depth(self.current)
+    }
+
+    /// Moves to any node of the tree, restoring its state and turn.
+    pub fn go_to(&mut self, id: NodeId) {
+        let node = self.tree.get(id);
+        self.state = node.state.clone();
+        self.turn = node.turn;
+        self.current = id;
+        self.tree.select(id);
+        self.collect_positions();
+        self.update_status();
+    }
+
+    /// Goes back to the position before the last move. Returns whether there was a move to go back from.
+    pub fn undo(&mut self) -> bool {
+        // a resignation has no node of its own, so taking it back only reopens the game
+        if self.tree.get(self.current).resigned {
+            self.tree.get_mut(self.current).resigned = false;
+            self.info.result.clear();
+            self.update_status();
+            return true;
+        }
+
+        match self.tree.get(self.current).parent {
+            Some(parent) => {
+                self.go_to(parent);
+                true
             }
+            None => false,
+        }
+    }
 
-            self.history.push(self.state.clone());
+    /// Goes forward along the line that was visited last. Returns whether there was a move to go forward to.
+    pub fn redo(&mut self) -> bool {
+        match self.tree.get(self.current).selected_child() {
+            Some(child) => {
+                self.go_to(child);
+                true
+            }
+            None => false,
+        }
+    }
 
-            self.state.board[i] = Some(self.turn);
-            for np in p.neighbors(self.size) {
-                if self.stone_at(np) == Some(-self.turn) {
-                    self.remove_if_surrounded(np);
-                }
+    /// Goes backward or forward along the current line until `n` moves have been played, or as close to that as possible.
+    pub fn go_to_move(&mut self, n: usize) {
+        let mut target = self.current;
+        let depth = self.move_number();
+        if depth > n {
+            target = self.tree.ancestors(target).nth(depth - n).unwrap();
+        }
+        for _ in depth..n {
+            match self.tree.get(target).selected_child() {
+                Some(child) => target = child,
+                None => break,
+            }
+        }
+        if target != self.current {
+            self.go_to(target);
+        }
+    }
+
+    /// Goes back to the position before the first move.
+    pub fn first(&mut self) {
+        self.go_to(GameTree::ROOT);
+    }
+
+    /// Goes forward to the end of the current line.
+    pub fn last(&mut self) {
+        while self.redo() {}
+    }
+
+    /// The variations of the last move, including the current position itself. Empty at the root.
+    pub fn siblings(&self) -> &[NodeId] {
+        match self.tree.get(self.current).parent {
+            Some(parent) => &self.tree.get(parent).children,
+            None => &[],
+        }
+    }
+
+    /// Switches to the previous (`offset` -1) or next (`offset` 1) variation of the last move, keeping the move number.
+    pub fn switch_variation(&mut self, offset: isize) -> bool {
+        let siblings = self.siblings();
+        let i = siblings
+            .iter()
+            .position(|&id| id == self.current)
+            .unwrap_or(0) as isize
+            + offset;
+        match siblings.get(i as usize) {
+            Some(&sibling) if i >= 0 => {
+                self.go_to(sibling);
+                true
             }
-            self.remove_if_surrounded(p);
+            _ => false,
+        }
+    }
+
+    /// Makes the line leading to the current position the main line.
+    pub fn promote_variation(&mut self) {
+        self.tree.promote(self.current);
+    }
 
-            // ko rule
-            let len = self.history.len();
-            if len >= 2
-                && self.history.get(len - 2).map(|s| s.board.clone())
-                    == Some(self.state.board.clone())
-            {
-                self.state = self.history.pop().unwrap();
-                return;
+    /// Deletes the current position and every move after it, going back to the position before. The root cannot be deleted.
+    pub fn delete_branch(&mut self) -> bool {
+        match self.tree.get(self.current).parent {
+            Some(parent) => {
+                let remap = self.tree.remove(self.current);
+                self.go_to(remap[parent].unwrap());
+                true
             }
+            None => false,
+        }
+    }
 
-            self.turn = -self.turn;
+    /// Derives the status from the moves leading here, after moving through the tree. A resignation in this position
+    /// finishes the game again; a result that was agreed upon no longer shows in the status, but stays recorded in
+    /// `info`.
+    fn update_status(&mut self) {
+        let node = self.tree.get(self.current);
+        if node.resigned {
+            self.end(GameResult::Resignation { winner: -node.turn });
+            return;
         }
+
+        self.status = if self.consecutive_passes() >= 2 {
+            GameStatus::Scoring
+        } else {
+            GameStatus::Playing
+        };
+    }
+
+    /// Number of passes in a row that led to the current position.
+    pub fn consecutive_passes(&self) -> usize {
+        self.tree
+            .ancestors(self.current)
+            .map_while(|id| self.tree.get(id).mv)
+            .take_while(|&(_, mv)| mv == Move::Pass)
+            .count()
+    }
+
+    /// Ends the scoring phase with the result the players agreed upon.
+    pub fn finish(&mut self, result: GameResult) {
+        if self.status == GameStatus::Scoring {
+            self.end(result);
+        }
+    }
+
+    /// Finishes the game, and records the result in `info`.
+    fn end(&mut self, result: GameResult) {
+        self.status = GameStatus::Finished(result);
+        self.info.result = result.to_string();
+    }
+
+    /// Leaves the scoring phase when the players disagree about the status of some stones, and play continues. Any
+    /// result recorded for the game no longer holds.
+    pub fn resume(&mut self) {
+        if self.status == GameStatus::Scoring {
+            self.status = GameStatus::Playing;
+            self.info.result.clear();
+        }
+    }
+
+    /// The color and stones of the chain at `p`, if there is a stone there.
+    pub fn chain_at(&self, p: Pos) -> Option<(Stone, Vec<Pos>)> {
+        let board = &self.state.board;
+        let i = p.index(self.size)?;
+        let color = board[i]?;
+        Some((color, board.chain(i).map(|i| board.pos(i)).collect()))
+    }
+
+    /// Every chain on the board with its color, ordered by their first stones.
+    pub fn chains(&self) -> Vec<(Stone, Vec<Pos>)> {
+        let board = &self.state.board;
+        (0..board.len())
+            .filter(|&i| board[i].is_some() && board.chain(i).all(|j| j >= i))
+            .filter_map(|i| self.chain_at(board.pos(i)))
+            .collect()
+    }
+
+    /// The empty points next to the chain at `p`, if there is a stone there.
+    pub fn liberties(&self, p: Pos) -> Option<Vec<Pos>> {
+        let board = &self.state.board;
+        let i = p.index(self.size)?;
+        board[i]?;
+        Some(
+            board
+                .liberties(i)
+                .into_iter()
+                .map(|i| board.pos(i))
+                .collect(),
+        )
+    }
+
+    /// The chains of `color` with a single liberty left, which the opponent can capture with their next move.
+    pub fn groups_in_atari(&self, color: Stone) -> Vec<Vec<Pos>> {
+        let board = &self.state.board;
+        self.chains()
+            .into_iter()
+            .filter(|(chain_color, chain)| {
+                *chain_color == color
+                    && board.liberties(chain[0].index(self.size).unwrap()).len() == 1
+            })
+            .map(|(_, chain)| chain)
+            .collect()
+    }
+
+    /// Checks whether the structure around position `p` is surrounded, and if so, returns the the whole structure.
+    pub fn is_surrounded(&self, p: Pos) -> Option<(Stone, Vec<Pos>)> {
+        let chain = self.chain_at(p)?;
+        let i = p.index(self.size)?;
+        if self.state.board.pseudo_liberties(i) > 0 {
+            return None;
+        }
+
+        Some(chain)
+    }
+
+    pub fn try_place_stone(&mut self, p: Pos) -> Result<MoveOutcome, IllegalMove> {
+        self.play(Move::Place(p))
+    }
+
+    /// Why the side to move may not place a stone at `p`, if it may not, without changing anything.
+    pub fn check_place(&self, p: Pos) -> Result<(), IllegalMove> {
+        if self.status != GameStatus::Playing {
+            return Err(IllegalMove::GameOver);
+        }
+        if self.handicap_to_place > 0 {
+            return self.check_handicap_stone(p);
+        }
+        self.check_stone(p).map(|_| ())
+    }
+
+    pub fn is_legal(&self, p: Pos) -> bool {
+        self.check_place(p).is_ok()
+    }
+
+    /// Every move the side to move may make: the points it may place a stone on, followed by passing unless handicap
+    /// stones still have to be placed. None once the game is over.
+    pub fn legal_moves(&self) -> Vec<Move> {
+        if self.status != GameStatus::Playing {
+            return vec![];
+        }
+
+        let mut moves: Vec<Move> = self
+            .size
+            .points()
+            .filter(|&p| self.is_legal(p))
+            .map(Move::Place)
+            .collect();
+        if self.handicap_to_place == 0 || self.handicap_placed() >= 2 {
+            moves.push(Move::Pass);
+        }
+        moves
+    }
+
+    /// Checks whether placing a stone at `p` breaks any rule, returning the index of `p` if it does not.
+    fn check_stone(&self, p: Pos) -> Result<usize, IllegalMove> {
+        let i = p.index(self.size).ok_or(IllegalMove::OutOfBounds)?;
+        if self.has_stone_at(p) {
+            return Err(IllegalMove::Occupied);
+        }
+        let board = &self.state.board;
+        if board.is_suicide(i, self.turn) {
+            let alone = board
+                .neighbors(i)
+                .iter()
+                .all(|&n| board[n] != Some(self.turn));
+            let allowed = if alone {
+                self.ruleset.allows_single_stone_suicide()
+            } else {
+                self.ruleset.allows_suicide()
+            };
+            if !allowed {
+                return Err(IllegalMove::Suicide);
+            }
+        }
+        if self.repeats_position(i) {
+            return Err(IllegalMove::Ko);
+        }
+        Ok(i)
+    }
+
+    /// Places a stone for the side to move and takes off whatever it captures. The board is only changed if the move is legal.
+    fn place_stone(&mut self, p: Pos) -> Result<MoveOutcome, IllegalMove> {
+        let i = self.check_stone(p)?;
+
+        let removed = self.state.board.play(i, self.turn);
+        self.state.captures[-self.turn] += removed.captured.len();
+        self.state.captures[self.turn] += removed.suicide.len();
+
+        let board = &self.state.board;
+        Ok(MoveOutcome {
+            captured: removed.captured.iter().map(|&i| board.pos(i)).collect(),
+            suicide: removed.suicide.iter().map(|&i| board.pos(i)).collect(),
+        })
+    }
+
+    /// Checks whether placing a stone at `i` would break the ko rule of the ruleset.
+    fn repeats_position(&self, i: usize) -> bool {
+        let positional = match self.ruleset.superko() {
+            Superko::None => return self.state.board.ko() == Some(i),
+            Superko::Positional => true,
+            Superko::Situational => false,
+        };
+
+        // equal hashes are taken to mean equal positions; a collision is astronomically unlikely
+        let hash = self.state.board.hash_after(i, self.turn);
+        let next = -self.turn;
+        self.positions.contains(&(hash ^ zobrist::turn(next)))
+            || (positional && self.positions.contains(&(hash ^ zobrist::turn(self.turn))))
     }
 
     pub fn stone_at(&self, p: Pos) -> Option<Stone> {
@@ -182,6 +931,569 @@ impl Game {
     }
 
     pub fn has_stone_at(&self, p: Pos) -> bool {
-        None != self.stone_at(p)
+        self.stone_at(p).is_some()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram;
+
+    fn game(diagram: &str, ruleset: Ruleset) -> Game {
+        let mut game = diagram::game(diagram);
+        game.ruleset = ruleset;
+        game
+    }
+
+    fn place(game: &mut Game, x: i32, y: i32) -> Result<MoveOutcome, IllegalMove> {
+        game.play(Move::Place(Pos(x, y)))
+    }
+
+    /// Black takes the ko at C4, after which White may retake at B4 only once the position changed.
+    const KO: &str = "
+        . X O . .
+        X O . O .
+        . X O . .
+        . . . . .
+        . . . . .
+    ";
+
+    #[test]
+    fn ko_is_not_retaken_right_away() {
+        for ruleset in Ruleset::ALL {
+            let mut game = game(KO, ruleset);
+            assert_eq!(place(&mut game, 2, 1).unwrap().captured, vec![Pos(1, 1)]);
+            assert_eq!(
+                place(&mut game, 1, 1).err(),
+                Some(IllegalMove::Ko),
+                "{}",
+                ruleset
+            );
+
+            place(&mut game, 4, 4).unwrap();
+            place(&mut game, 4, 3).unwrap();
+            assert!(place(&mut game, 1, 1).is_ok(), "{}", ruleset);
+        }
+    }
+
+    #[test]
+    fn superko_forbids_retaking_after_passes() {
+        for ruleset in Ruleset::ALL {
+            let mut game = game(KO, ruleset);
+            place(&mut game, 2, 1).unwrap();
+            game.play(Move::Pass).unwrap();
+            game.play(Move::Pass).unwrap();
+            game.resume();
+
+            // the passes forget the ko, but the retake repeats the first position with Black to move
+            let retake = place(&mut game, 1, 1);
+            match ruleset.superko() {
+                Superko::None => assert!(retake.is_ok(), "{}", ruleset),
+                _ => assert_eq!(retake.err(), Some(IllegalMove::Ko), "{}", ruleset),
+            }
+        }
+    }
+
+    #[test]
+    fn situational_superko_looks_at_who_is_to_move() {
+        for ruleset in Ruleset::ALL {
+            let mut game = game(
+                "
+                . X . O .
+                X O O . .
+                . . . . .
+                . . . . .
+                . . . . .
+                ",
+                ruleset,
+            );
+            place(&mut game, 2, 0).unwrap();
+            assert_eq!(place(&mut game, 0, 0).unwrap().captured.len(), 2);
+
+            // brings back the first position, but with White to move this time
+            let recapture = place(&mut game, 1, 0);
+            match ruleset.superko() {
+                Superko::Positional => {
+                    assert_eq!(recapture.err(), Some(IllegalMove::Ko), "{}", ruleset)
+                }
+                _ => assert!(recapture.is_ok(), "{}", ruleset),
+            }
+        }
+    }
+
+    #[test]
+    fn suicide_only_where_the_rules_allow_it() {
+        const POSITION: &str = "
+            .(X). X
+            X X O X
+            . . X .
+        ";
+        for ruleset in Ruleset::ALL {
+            let mut game = game(POSITION, ruleset);
+            assert_eq!(game.turn, Stone::White);
+
+            // takes the stone at C2 along
+            let played = place(&mut game, 2, 0);
+            if ruleset.allows_suicide() {
+                assert_eq!(played.unwrap().suicide.len(), 2, "{}", ruleset);
+                assert!(!game.has_stone_at(Pos(2, 1)));
+                assert_eq!(game.state.captures[Stone::White], 2);
+            } else {
+                assert_eq!(played.err(), Some(IllegalMove::Suicide), "{}", ruleset);
+            }
+
+            // a single stone changes nothing, which positional superko forbids in turn
+            let mut game = self::game(POSITION, ruleset);
+            let played = place(&mut game, 0, 0);
+            match (ruleset.allows_single_stone_suicide(), ruleset.superko()) {
+                (false, _) => assert_eq!(played.err(), Some(IllegalMove::Suicide), "{}", ruleset),
+                (true, Superko::Positional) => {
+                    assert_eq!(played.err(), Some(IllegalMove::Ko), "{}", ruleset)
+                }
+                (true, _) => assert_eq!(played.unwrap().suicide, vec![Pos(0, 0)]),
+            }
+        }
+    }
+
+    #[test]
+    fn undo_takes_back_a_resignation_played_here() {
+        let mut game = Game::new(9);
+        place(&mut game, 4, 4).unwrap();
+        game.play(Move::Resign).unwrap();
+        assert_eq!(game.info.result, "B+R");
+
+        assert!(game.undo());
+        assert_eq!(game.move_number(), 1);
+        assert_eq!(game.status, GameStatus::Playing);
+        assert!(game.info.result.is_empty());
+        assert!(game.undo());
+        assert_eq!(game.move_number(), 0);
+    }
+
+    #[test]
+    fn resignation_stays_with_its_position_while_browsing() {
+        let mut game = Game::new(9);
+        place(&mut game, 4, 4).unwrap();
+        place(&mut game, 2, 2).unwrap();
+        game.play(Move::Resign).unwrap();
+
+        game.first();
+        assert_eq!(game.status, GameStatus::Playing);
+        game.last();
+        assert_eq!(
+            game.status,
+            GameStatus::Finished(GameResult::Resignation {
+                winner: Stone::White
+            })
+        );
+        assert_eq!(game.info.result, "W+R");
+
+        assert!(game.undo());
+        assert_eq!(game.move_number(), 2);
+        assert_eq!(game.status, GameStatus::Playing);
+        assert!(game.info.result.is_empty());
+    }
+
+    #[test]
+    fn going_to_a_move_keeps_the_resignation() {
+        let mut game = Game::new(9);
+        place(&mut game, 4, 4).unwrap();
+        place(&mut game, 2, 2).unwrap();
+        game.play(Move::Resign).unwrap();
+
+        game.go_to_move(0);
+        assert_eq!(game.move_number(), 0);
+        assert_eq!(game.status, GameStatus::Playing);
+        game.go_to_move(2);
+        assert_eq!(game.move_number(), 2);
+        assert_eq!(
+            game.status,
+            GameStatus::Finished(GameResult::Resignation {
+                winner: Stone::White
+            })
+        );
+        assert_eq!(game.info.result, "W+R");
+
+        game.go_to_move(2);
+        assert_eq!(game.info.result, "W+R");
+        game.go_to_move(5);
+        assert_eq!(game.move_number(), 2);
+        game.go_to_move(1);
+        assert_eq!(game.move_number(), 1);
+        assert_eq!(game.status, GameStatus::Playing);
+    }
+
+    #[test]
+    fn undo_goes_back_a_move_in_a_resigned_record() {
+        let mut game = crate::sgf::parse("(;SZ[9]RE[B+R];B[ee];W[cc])").unwrap();
+        assert!(game.undo());
+        assert_eq!(game.move_number(), 1);
+        assert_eq!(game.info.result, "B+R");
+    }
+
+    #[test]
+    fn new_moves_drop_the_result_and_browsing_keeps_it() {
+        let mut game = Game::new(9);
+        place(&mut game, 4, 4).unwrap();
+        game.play(Move::Pass).unwrap();
+        game.play(Move::Pass).unwrap();
+        game.finish(GameResult::Score {
+            winner: Stone::Black,
+            margin: 74.5,
+        });
+
+        game.first();
+        place(&mut game, 4, 4).unwrap();
+        assert_eq!(game.info.result, "B+74.5");
+        game.undo();
+        place(&mut game, 2, 2).unwrap();
+        assert_eq!(game.status, GameStatus::Playing);
+        assert!(game.info.result.is_empty());
+    }
+
+    /// Three variations of the first move: E5, C3 and G7, with node ids 1 to 3.
+    fn variations() -> Game {
+        let mut game = Game::new(9);
+        place(&mut game, 4, 4).unwrap();
+        game.undo();
+        place(&mut game, 2, 2).unwrap();
+        game.undo();
+        place(&mut game, 6, 6).unwrap();
+        game
+    }
+
+    #[test]
+    fn switching_variations_stops_at_the_first_and_last() {
+        let mut game = variations();
+        assert_eq!(game.siblings(), [1, 2, 3]);
+
+        assert!(!game.switch_variation(1));
+        assert_eq!(game.current(), 3);
+        assert!(game.switch_variation(-1));
+        assert_eq!(game.current(), 2);
+        assert_eq!(game.stone_at(Pos(2, 2)), Some(Stone::Black));
+        assert!(game.switch_variation(-1));
+        assert_eq!(game.current(), 1);
+        assert!(!game.switch_variation(-1));
+        assert_eq!(game.current(), 1);
+        assert_eq!(game.move_number(), 1);
+        assert_eq!(game.turn, Stone::White);
+
+        game.first();
+        assert!(!game.switch_variation(1));
+        assert_eq!(game.current(), GameTree::ROOT);
+    }
+
+    #[test]
+    fn promoting_makes_the_variation_the_main_line() {
+        let mut game = variations();
+        game.switch_variation(-1);
+        game.promote_variation();
+
+        assert_eq!(game.siblings(), [2, 1, 3]);
+        assert_eq!(game.current(), 2);
+        assert_eq!(game.tree().main_line_end(), 2);
+        game.first();
+        assert!(game.redo());
+        assert_eq!(game.current(), 2);
+    }
+
+    #[test]
+    fn deleting_a_branch_renumbers_the_rest_and_goes_back() {
+        let mut game = variations();
+        place(&mut game, 3, 3).unwrap();
+        game.go_to(1);
+        place(&mut game, 5, 5).unwrap();
+        assert_eq!(game.current(), 5);
+        game.undo();
+
+        assert!(game.delete_branch());
+        assert_eq!(game.current(), GameTree::ROOT);
+        assert!(game.state == *game.initial_state());
+        assert_eq!(game.tree().len(), 4);
+        assert_eq!(game.tree().get(GameTree::ROOT).children, [1, 2]);
+        assert_eq!(game.tree().get(3).parent, Some(2));
+        assert_eq!(
+            game.tree().get(3).mv,
+            Some((Stone::White, Move::Place(Pos(3, 3))))
+        );
+
+        assert!(game.redo());
+        assert_eq!(game.current(), 1);
+        assert_eq!(game.stone_at(Pos(2, 2)), Some(Stone::Black));
+        game.first();
+        assert!(!game.delete_branch());
+        assert_eq!(game.tree().len(), 4);
+    }
+
+    #[test]
+    fn handicap_only_before_any_move() {
+        let mut game = Game::new(9);
+        place(&mut game, 4, 4).unwrap();
+        assert!(!game.set_handicap(2, Placement::Fixed));
+        game.first();
+        assert!(!game.set_handicap(2, Placement::Fixed));
+        assert_eq!(game.tree().len(), 2);
+
+        let mut game = Game::new(9);
+        assert!(game.set_handicap(2, Placement::Fixed));
+        assert_eq!(game.turn, Stone::White);
+        assert!(!game.set_handicap(3, Placement::Fixed));
+    }
+
+    #[test]
+    fn free_handicap_ended_early_counts_the_stones_placed() {
+        let mut game = Game::new(9);
+        assert!(game.set_handicap(3, Placement::Free));
+        place(&mut game, 2, 2).unwrap();
+        assert_eq!(game.handicap_to_place(), 2);
+        assert_eq!(
+            game.play(Move::Pass).err(),
+            Some(IllegalMove::HandicapNotPlaced)
+        );
+        assert!(!game.legal_moves().contains(&Move::Pass));
+        assert_eq!(game.turn, Stone::Black);
+
+        place(&mut game, 6, 6).unwrap();
+        game.play(Move::Pass).unwrap();
+        assert_eq!(game.handicap_to_place(), 0);
+        assert_eq!(game.info.handicap, 2);
+        assert_eq!(game.info.komi, 0.5);
+    }
+
+    /// Checks that the legality queries say exactly what playing each move on a copy of `game` says.
+    fn assert_legality_matches_play(game: &Game) {
+        let legal = game.legal_moves();
+        for p in game.size.points() {
+            let played = game.clone().play(Move::Place(p)).map(|_| ());
+            assert_eq!(game.check_place(p), played, "{:?}", p);
+            assert_eq!(game.is_legal(p), played.is_ok(), "{:?}", p);
+            assert_eq!(legal.contains(&Move::Place(p)), played.is_ok(), "{:?}", p);
+        }
+        let passed = game.clone().play(Move::Pass).is_ok();
+        assert_eq!(legal.contains(&Move::Pass), passed);
+    }
+
+    /// Plays `moves` one after the other, checking the legality queries before each of them and at the end.
+    fn check_legality_along(game: &mut Game, moves: &[Move]) {
+        for &mv in moves {
+            assert_legality_matches_play(game);
+            game.play(mv).unwrap();
+        }
+        assert_legality_matches_play(game);
+    }
+
+    #[test]
+    fn legality_queries_agree_with_playing() {
+        for ruleset in Ruleset::ALL {
+            // a ko taken and retaken after a move elsewhere, then passes that leave the retake to superko
+            let mut game = game(KO, ruleset);
+            let moves = [
+                Move::Place(Pos(2, 1)),
+                Move::Place(Pos(4, 4)),
+                Move::Place(Pos(4, 3)),
+                Move::Place(Pos(1, 1)),
+                Move::Place(Pos(0, 4)),
+                Move::Pass,
+                Move::Pass,
+            ];
+            check_legality_along(&mut game, &moves);
+            game.resume();
+            assert_legality_matches_play(&game);
+
+            let mut game = self::game(
+                "
+                .(X). X
+                X X O X
+                . . X .
+                ",
+                ruleset,
+            );
+            check_legality_along(&mut game, &[Move::Place(Pos(0, 2))]);
+
+            let mut game = Game::with_ruleset(5, ruleset);
+            assert!(game.set_handicap(3, Placement::Free));
+            check_legality_along(
+                &mut game,
+                &[Move::Place(Pos(1, 1)), Move::Place(Pos(3, 3)), Move::Pass],
+            );
+        }
+    }
+
+    /// Two chains in atari, one of each color, and a black chain whose stones share a liberty at C3.
+    const CHAINS: &str = "
+        X X . . .
+        O O O . .
+        . . . X X
+        . . X X O
+        O . . . .
+    ";
+
+    fn sorted(mut points: Vec<Pos>) -> Vec<Pos> {
+        points.sort_by_key(|p| (p.1, p.0));
+        points
+    }
+
+    #[test]
+    fn liberties_count_every_point_once() {
+        let game = game(CHAINS, Ruleset::default());
+        let liberties = vec![
+            Pos(3, 1),
+            Pos(4, 1),
+            Pos(2, 2),
+            Pos(1, 3),
+            Pos(2, 4),
+            Pos(3, 4),
+        ];
+        assert_eq!(sorted(game.liberties(Pos(3, 3)).unwrap()), liberties);
+        assert_eq!(sorted(game.liberties(Pos(2, 3)).unwrap()), liberties);
+        assert_eq!(game.liberties(Pos(4, 3)), Some(vec![Pos(4, 4)]));
+        assert_eq!(game.liberties(Pos(2, 2)), None);
+    }
+
+    #[test]
+    fn chains_come_in_the_order_of_their_first_stones() {
+        let game = game(CHAINS, Ruleset::default());
+        let chains: Vec<(Stone, Vec<Pos>)> = game
+            .chains()
+            .into_iter()
+            .map(|(color, chain)| (color, sorted(chain)))
+            .collect();
+        assert_eq!(
+            chains,
+            vec![
+                (Stone::Black, vec![Pos(0, 0), Pos(1, 0)]),
+                (Stone::White, vec![Pos(0, 1), Pos(1, 1), Pos(2, 1)]),
+                (
+                    Stone::Black,
+                    vec![Pos(3, 2), Pos(4, 2), Pos(2, 3), Pos(3, 3)]
+                ),
+                (Stone::White, vec![Pos(4, 3)]),
+                (Stone::White, vec![Pos(0, 4)]),
+            ]
+        );
+        assert_eq!(
+            game.chain_at(Pos(1, 1))
+                .map(|(color, chain)| (color, sorted(chain))),
+            Some(chains[1].clone())
+        );
+        assert_eq!(game.chain_at(Pos(4, 4)), None);
+    }
+
+    #[test]
+    fn chains_with_one_liberty_are_in_atari() {
+        let game = game(CHAINS, Ruleset::default());
+        let in_atari = |color| -> Vec<Vec<Pos>> {
+            game.groups_in_atari(color)
+                .into_iter()
+                .map(sorted)
+                .collect()
+        };
+        assert_eq!(in_atari(Stone::Black), vec![vec![Pos(0, 0), Pos(1, 0)]]);
+        assert_eq!(in_atari(Stone::White), vec![vec![Pos(4, 3)]]);
+    }
+
+    #[cfg(feature = "serde")]
+    mod saved {
+        use super::*;
+        use serde_json::{json, Value};
+
+        fn load(value: Value) -> Result<Game, serde_json::Error> {
+            serde_json::from_value(value)
+        }
+
+        /// A handicap game with a variation, saved in the middle of its main line.
+        fn saved() -> Value {
+            let mut game = Game::with_topology(9, Ruleset::Chinese, Topology::Cylinder);
+            assert!(game.set_handicap(2, Placement::Fixed));
+            place(&mut game, 4, 4).unwrap();
+            place(&mut game, 0, 4).unwrap();
+            game.undo();
+            place(&mut game, 8, 4).unwrap();
+            place(&mut game, 4, 2).unwrap();
+            game.undo();
+            serde_json::to_value(&game).unwrap()
+        }
+
+        #[test]
+        fn games_round_trip() {
+            let value = saved();
+            let game = load(value.clone()).unwrap();
+            assert_eq!(serde_json::to_value(&game).unwrap(), value);
+            assert_eq!(*game.topology(), Topology::Cylinder);
+            assert_eq!(game.info.handicap, 2);
+            assert_eq!(game.move_number(), 2);
+            assert_eq!(game.tree().len(), 5);
+            assert_eq!(game.siblings().len(), 2);
+            assert!(game.clone().redo());
+            // the cylinder joins the left and right edges
+            assert_eq!(game.liberties(Pos(8, 4)).unwrap().len(), 4);
+            assert_eq!(
+                game.hash(),
+                game.tree().get(game.current()).state.hash(game.turn)
+            );
+        }
+
+        #[test]
+        fn resignations_round_trip() {
+            let mut game = Game::new(9);
+            place(&mut game, 4, 4).unwrap();
+            game.play(Move::Resign).unwrap();
+
+            let mut game = load(serde_json::to_value(&game).unwrap()).unwrap();
+            assert!(matches!(game.status, GameStatus::Finished(_)));
+            assert!(game.undo());
+            assert_eq!(game.move_number(), 1);
+            assert_eq!(game.status, GameStatus::Playing);
+        }
+
+        #[test]
+        fn broken_games_are_refused() {
+            let broken = |path: &str, value: Value| {
+                let mut saved = saved();
+                *saved.pointer_mut(path).unwrap() = value;
+                load(saved).err().map(|err| err.to_string())
+            };
+
+            assert!(broken("/version", json!(0)).is_some());
+            assert!(broken("/current", json!(5)).is_some());
+            assert!(broken("/tree/1/mv", Value::Null).is_some());
+            assert!(broken("/tree/1/mv/1", json!({ "place": [9, 4] }))
+                .is_some_and(|err| err.contains("off the board")));
+            assert!(broken("/tree/1/mv/1", json!("resign")).is_some());
+            assert!(broken("/tree/1/state/board/ko", json!(81)).is_some());
+            assert!(broken("/tree/1/state/board/stones", json!("X")).is_some());
+            assert!(broken(
+                "/tree/1/state/board/size",
+                json!({ "width": 9, "height": 8 })
+            )
+            .is_some());
+        }
+
+        #[test]
+        fn graphs_that_do_not_fit_are_refused() {
+            let game = Game::with_topology(
+                (3, 1),
+                Ruleset::default(),
+                Topology::graph((3, 1).into(), &[(Pos(0, 0), Pos(1, 0))]).unwrap(),
+            );
+            let mut saved = serde_json::to_value(&game).unwrap();
+            assert!(load(saved.clone()).is_ok());
+
+            // Black at 0 would be captured by White at 1 with 2 still empty
+            saved["topology"] = json!({ "graph": [[2], [0], [0]] });
+            assert!(load(saved.clone()).is_err());
+            saved["topology"] = json!({ "graph": [[1, 1], [0, 0], []] });
+            assert!(load(saved).is_err());
+        }
+
+        #[test]
+        fn boards_bigger_than_sgf_allows_load() {
+            let mut game = Game::new(60);
+            place(&mut game, 55, 55).unwrap();
+            let game = load(serde_json::to_value(&game).unwrap()).unwrap();
+            assert!(game.has_stone_at(Pos(55, 55)));
+        }
     }
 }
@@ -0,0 +1,123 @@
+//! Handicap stones, which Black gets before White's first move to even out a difference in strength.
+
+use crate::game::{BoardSize, Pos};
+
+/// How the handicap stones are put on the board.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Placement {
+    /// On the star points, in the standard order.
+    #[default]
+    Fixed,
+    /// Wherever Black likes, by playing them one at a time like moves.
+    Free,
+}
+
+/// The most stones there are star points for.
+pub const MAX_FIXED: usize = 9;
+
+/// The star points for `stones` fixed handicap stones, in the order GTP's `fixed_handicap` gives them on square boards.
+/// `None` if the board is too small for that many: boards narrower than 7 lines have no star points, and an even number
+/// of lines lacks the middle line.
+pub fn fixed_points(size: BoardSize, stones: usize) -> Option<Vec<Pos>> {
+    let BoardSize { width, height } = size;
+    let odd = width % 2 == 1 && height % 2 == 1;
+    if width.min(height) < 7 || !(2..=MAX_FIXED).contains(&stones) || (!odd && stones > 4) {
+        return None;
+    }
+
+    // third line on small boards, fourth line from 13 lines up
+    let edge = |lines: usize| if lines >= 13 { 3 } else { 2 };
+    let (left, right, center) = (edge(width), width - 1 - edge(width), width / 2);
+    let (top, bottom, middle) = (edge(height), height - 1 - edge(height), height / 2);
+
+    // GTP starts at the bottom left
+    let mut points = vec![(left, bottom), (right, top)];
+    if stones >= 3 {
+        points.push((left, top));
+    }
+    if stones >= 4 {
+        points.push((right, bottom));
+    }
+    if stones >= 6 {
+        points.extend([(left, middle), (right, middle)]);
+    }
+    if stones >= 8 {
+        points.extend([(center, bottom), (center, top)]);
+    }
+    if stones >= 5 && stones % 2 == 1 {
+        points.push((center, middle));
+    }
+
+    Some(points.into_iter().map(Pos::from).collect())
+}
+
+/// Points for `stones` handicap stones placed freely, spread out over a board of `size`: the star points as far as
+/// they go, and then each next stone on the point farthest from both the others and the edge. `None` unless there are
+/// at least 2 stones and fewer than there are points.
+pub fn spread_points(size: BoardSize, stones: usize) -> Option<Vec<Pos>> {
+    if stones < 2 || stones >= size.len() {
+        return None;
+    }
+    let mut points = (2..=stones.min(MAX_FIXED))
+        .rev()
+        .find_map(|stones| fixed_points(size, stones))
+        .unwrap_or_default();
+
+    // how far every point is from the nearest stone, where the edge counts as one just off the board
+    let distance = |p: Pos, q: Pos| p.0.abs_diff(q.0) + p.1.abs_diff(q.1);
+    let line = |p: Pos| {
+        let (x, y) = (p.0 as usize, p.1 as usize);
+        x.min(size.width - 1 - x).min(y).min(size.height - 1 - y)
+    };
+    let mut nearest: Vec<u32> = size
+        .points()
+        .map(|p| {
+            points
+                .iter()
+                .map(|&q| distance(p, q))
+                .fold(line(p) as u32 + 1, u32::min)
+        })
+        .collect();
+
+    while points.len() < stones {
+        let i = (0..size.len())
+            .filter(|&i| nearest[i] > 0)
+            .max_by_key(|&i| (nearest[i], line(size.pos(i)), std::cmp::Reverse(i)))?;
+        let p = size.pos(i);
+        points.push(p);
+        for (j, q) in size.points().enumerate() {
+            nearest[j] = nearest[j].min(distance(p, q));
+        }
+    }
+    Some(points)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spread_points_fill_any_board() {
+        for size in [
+            BoardSize::square(9),
+            BoardSize::square(8),
+            BoardSize::square(3),
+        ] {
+            for stones in 2..size.len() {
+                let points = spread_points(size, stones).unwrap();
+                assert_eq!(points.len(), stones);
+                assert!(points.iter().all(|p| p.valid(size)));
+                assert!((1..points.len()).all(|k| !points[..k].contains(&points[k])));
+            }
+            assert_eq!(spread_points(size, size.len()), None);
+        }
+        assert_eq!(spread_points(BoardSize::square(9), 1), None);
+    }
+
+    #[test]
+    fn spread_points_start_at_the_star_points() {
+        let size = BoardSize::square(19);
+        let points = spread_points(size, 12).unwrap();
+        assert_eq!(points[..9], fixed_points(size, 9).unwrap()[..]);
+    }
+}
@@ -0,0 +1,59 @@
+use crate::game::Stone;
+use enum_map::EnumMap;
+
+#[derive(Clone, Debug, Default, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data, druid::Lens))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlayerInfo {
+    pub name: String,
+    /// Free-form, e.g. `5k` or `3d`.
+    pub rank: String,
+}
+
+/// What a game record says about the game besides its moves, as in SGF's root properties.
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "druid", derive(druid::Data, druid::Lens))]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GameInfo {
+    /// Points White gets for moving second.
+    pub komi: f64,
+    /// Number of handicap stones Black received, as recorded in SGF's `HA`.
+    pub handicap: usize,
+    #[cfg_attr(feature = "druid", data(eq))]
+    pub players: EnumMap<Stone, PlayerInfo>,
+    /// Free-form, but usually `YYYY-MM-DD`.
+    pub date: String,
+    pub event: String,
+    /// The result as recorded, e.g. `B+R` or `W+T`; empty if unknown.
+    pub result: String,
+    /// Main time per player, in seconds.
+    pub time_limit: Option<f64>,
+    /// Free-form, e.g. `5x30 byo-yomi`.
+    pub overtime: String,
+}
+
+impl Default for GameInfo {
+    fn default() -> Self {
+        Self {
+            komi: 6.5,
+            handicap: 0,
+            players: EnumMap::default(),
+            date: String::new(),
+            event: String::new(),
+            result: String::new(),
+            time_limit: None,
+            overtime: String::new(),
+        }
+    }
+}
+
+impl GameInfo {
+    /// The player's name, or the color's if it is not known.
+    pub fn name(&self, color: Stone) -> &str {
+        match (self.players[color].name.as_str(), color) {
+            ("", Stone::Black) => "Black",
+            ("", Stone::White) => "White",
+            (name, _) => name,
+        }
+    }
+}
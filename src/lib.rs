@@ -0,0 +1,22 @@
+//! The Go engine behind the board UI, the GTP engine and the bots. With the `druid` feature the game types also
+//! implement druid's `Data` and `Lens`; without it, which is the default, nothing here depends on a GUI toolkit.
+
+#[macro_use]
+extern crate enum_map;
+
+pub mod benson;
+pub mod board;
+pub mod coords;
+pub mod diagram;
+pub mod estimate;
+pub mod game;
+pub mod handicap;
+pub mod info;
+pub mod mcts;
+pub mod player;
+pub mod rules;
+pub mod scoring;
+pub mod sgf;
+pub mod topology;
+pub mod tree;
+pub mod zobrist;
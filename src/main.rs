@@ -1,45 +1,152 @@
 // On Windows platform, don't show a console when opening the app.
 #![windows_subsystem = "windows"]
 
-#[macro_use]
-extern crate enum_map;
-
 mod flexbox;
-mod game;
 
-use crate::game::{Game, Pos};
 use druid::kurbo::{Circle, Line};
-use druid::piet::{LineCap, LineJoin, StrokeStyle};
-use druid::widget::{Label, LensWrap};
+use druid::piet::{
+    FontFamily, LineCap, LineJoin, StrokeStyle, Text, TextLayout, TextLayoutBuilder,
+};
+use druid::widget::{Button, CrossAxisAlignment, Either, Flex, Label};
 use druid::{
-    AppLauncher, Color, Data, Event, Lens, LensExt, MouseButton, PlatformError, Point,
-    RenderContext, Size, Widget, WindowDesc,
+    AppDelegate, AppLauncher, Color, Command, Data, DelegateCtx, Env, Event, ExtEventSink, Handled,
+    KbKey, Lens, MouseButton, PlatformError, Point, Rect, RenderContext, Selector, Size, Target,
+    Vec2, Widget, WindowDesc,
 };
+use druidgo::coords::Notation;
+use druidgo::estimate::{self, Estimate};
+use druidgo::game::{BoardSize, Game, GameStatus, IllegalMove, Move, Pos, Stone};
+use druidgo::mcts::{Budget, Config, MctsPlayer};
+use druidgo::player::Player;
+use druidgo::rules::Ruleset;
+use druidgo::scoring::Marking;
+use druidgo::sgf;
+use druidgo::topology::Topology;
+use druidgo::tree::NodeId;
 use flexbox::FlexBox;
-use game::{GameState, Stone};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
 
 #[derive(Clone, Data, Lens)]
 struct ViewModel {
     padding: f64,
     game: Game,
     hover: Option<Pos>,
+    /// Why the last click did not place a stone, if it didn't.
+    #[data(eq)]
+    illegal_move: Option<IllegalMove>,
+    /// The dead stones marked after both players passed.
+    #[data(eq)]
+    marking: Marking,
+    /// Of the current position, kept up to date by `position_changed`.
+    #[data(eq)]
+    estimate: Estimate,
+    /// The side the computer plays, if any.
+    computer: Option<Stone>,
+    /// Shared with the thread it thinks on, and kept between moves so that it can reuse its search.
+    #[data(ignore)]
+    bot: Arc<Mutex<MctsPlayer>>,
+    /// Whether the computer is thinking about a move, during which the board takes no moves.
+    thinking: bool,
 }
 
+/// Sent by the thread the computer thinks on: the position it thought about, and its move.
+const COMPUTER_MOVE: Selector<(NodeId, Move)> = Selector::new("druidgo.computer-move");
+
+/// Long enough to play reasonably on small boards.
+const THINKING_TIME: Duration = Duration::from_secs(3);
+
+/// Enough random games to tell dead stones apart, but few enough to keep the board responsive.
+const PLAYOUTS: usize = 32;
+
 impl ViewModel {
-    fn project(&self, widget_size: Size, pos: Pos) -> Point {
-        let Size { width, height } = widget_size;
-        let (widget_size, px, py) = if width > height {
-            (height, (width - height) / 2.0, 0.0)
-        } else {
-            (width, 0.0, (height - width) / 2.0)
+    fn new(game: Game) -> Self {
+        let estimate = estimate::estimate(&game.state, game.turn, PLAYOUTS);
+        let mut marking = Marking::default();
+        if game.status == GameStatus::Scoring {
+            marking.dead = estimate.dead();
+        }
+
+        Self {
+            padding: 8.0,
+            game,
+            hover: None,
+            illegal_move: None,
+            marking,
+            estimate,
+            computer: None,
+            bot: Arc::new(Mutex::new(MctsPlayer::new(Config {
+                budget: Budget::Time(THINKING_TIME),
+                ..Config::default()
+            }))),
+            thinking: false,
+        }
+    }
+
+    fn is_scoring(&self) -> bool {
+        self.game.status == GameStatus::Scoring
+    }
+
+    /// Estimates the new position, and starts off the marking of dead stones with the estimate when scoring.
+    fn position_changed(&mut self) {
+        self.estimate = estimate::estimate(&self.game.state, self.game.turn, PLAYOUTS);
+        self.marking = Marking::default();
+        if self.is_scoring() {
+            self.marking.dead = self.estimate.dead();
+        }
+    }
+
+    fn is_computer_turn(&self) -> bool {
+        self.computer == Some(self.game.turn) && self.game.status == GameStatus::Playing
+    }
+
+    /// Lets the computer think about its move on another thread, if it is its turn. The move comes back
+    /// through `sink` as a `COMPUTER_MOVE`.
+    fn computer_moves(&mut self, sink: ExtEventSink) {
+        if self.thinking || !self.is_computer_turn() {
+            return;
+        }
+        self.thinking = true;
+
+        let (game, bot) = (self.game.clone(), self.bot.clone());
+        std::thread::spawn(move || {
+            let mv = bot.lock().unwrap().genmove(&game);
+            // fails only when the window is gone
+            sink.submit_command(COMPUTER_MOVE, (game.current(), mv), Target::Auto)
+                .ok();
+        });
+    }
+
+    /// Plays the move the computer thought of in position `node`, unless the game has moved on since.
+    fn computer_played(&mut self, node: NodeId, mv: Move) {
+        self.thinking = false;
+        if self.game.current() != node || !self.is_computer_turn() {
+            return;
+        }
+        if self.game.play(mv).is_ok() {
+            self.position_changed();
+        }
+    }
+
+    /// The size of one point on the board, and where the board starts, so that it fits `widget_size` centered.
+    fn geometry(&self, widget_size: Size) -> (f64, Point) {
+        let BoardSize { width, height } = self.game.size;
+        let stone_size = ((widget_size.width - 2.0 * self.padding) / width as f64)
+            .min((widget_size.height - 2.0 * self.padding) / height as f64);
+
+        let origin = Point {
+            x: (widget_size.width - stone_size * width as f64) / 2.0,
+            y: (widget_size.height - stone_size * height as f64) / 2.0,
         };
+        (stone_size, origin)
+    }
 
-        let board_size = widget_size - 2.0 * self.padding;
-        let stone_size = board_size / (self.game.size as f64);
+    fn project(&self, widget_size: Size, pos: Pos) -> Point {
+        let (stone_size, origin) = self.geometry(widget_size);
 
         Point {
-            x: px + self.padding + (pos.0 as f64 + 0.5) * stone_size,
-            y: py + self.padding + (pos.1 as f64 + 0.5) * stone_size,
+            x: origin.x + (pos.0 as f64 + 0.5) * stone_size,
+            y: origin.y + (pos.1 as f64 + 0.5) * stone_size,
         }
     }
 
@@ -50,18 +157,11 @@ impl ViewModel {
     }
 
     fn unproject(&self, widget_size: Size, pt: Point) -> Pos {
-        let Size { width, height } = widget_size;
-        let (widget_size, px, py) = if width > height {
-            (height, (width - height) / 2.0, 0.0)
-        } else {
-            (width, 0.0, (height - width) / 2.0)
-        };
-
-        let board_size = widget_size - 2.0 * self.padding;
+        let (stone_size, origin) = self.geometry(widget_size);
 
         Pos(
-            ((pt.x - self.padding - px) / board_size * (self.game.size as f64)).floor() as i32,
-            ((pt.y - self.padding - py) / board_size * (self.game.size as f64)).floor() as i32,
+            ((pt.x - origin.x) / stone_size).floor() as i32,
+            ((pt.y - origin.y) / stone_size).floor() as i32,
         )
     }
 
@@ -70,11 +170,19 @@ impl ViewModel {
     }
 }
 
-struct GoBoardWidget {}
+struct GoBoardWidget {
+    /// Whether to ring the stones in atari, and show the liberties of the chain under the mouse.
+    atari_hints: bool,
+}
 
 impl GoBoardWidget {
     fn new() -> Self {
-        Self {}
+        Self { atari_hints: false }
+    }
+
+    fn atari_hints(mut self, atari_hints: bool) -> Self {
+        self.atari_hints = atari_hints;
+        self
     }
 }
 
@@ -91,14 +199,50 @@ impl Widget<ViewModel> for GoBoardWidget {
                 model.hover = model.unproject_valid(ctx.size(), e.pos);
                 ctx.request_paint();
             }
+            // the computer thinks about the position on the board
+            Event::MouseDown(_) | Event::KeyDown(_) if model.thinking => {}
             Event::MouseDown(e) => {
                 if e.button == MouseButton::Left {
                     if let Some(pos) = model.unproject_valid(ctx.size(), e.pos) {
-                        model.game.try_place_stone(pos);
+                        if model.is_scoring() {
+                            model.marking.toggle(&model.game, pos);
+                        } else {
+                            model.illegal_move = model.game.try_place_stone(pos).err();
+                            if model.illegal_move.is_none() {
+                                model.position_changed();
+                                model.computer_moves(ctx.get_external_handle());
+                            }
+                        }
                         ctx.request_paint();
                     }
                 }
             }
+            Event::WindowConnected => {
+                // so that the arrow keys work without clicking the board first
+                ctx.request_focus();
+            }
+            Event::KeyDown(e) => {
+                match e.key {
+                    KbKey::ArrowLeft => {
+                        model.game.undo();
+                    }
+                    KbKey::ArrowRight => {
+                        model.game.redo();
+                    }
+                    KbKey::ArrowUp => {
+                        model.game.switch_variation(-1);
+                    }
+                    KbKey::ArrowDown => {
+                        model.game.switch_variation(1);
+                    }
+                    KbKey::Home => model.game.first(),
+                    KbKey::End => model.game.last(),
+                    _ => return,
+                }
+                model.illegal_move = None;
+                model.position_changed();
+                ctx.request_paint();
+            }
             _ => {}
         }
     }
@@ -114,11 +258,15 @@ impl Widget<ViewModel> for GoBoardWidget {
 
     fn update(
         &mut self,
-        _ctx: &mut druid::UpdateCtx,
-        _old_model: &ViewModel,
-        _model: &ViewModel,
+        ctx: &mut druid::UpdateCtx,
+        old_model: &ViewModel,
+        model: &ViewModel,
         _env: &druid::Env,
     ) {
+        // e.g. after a button in the sidebar was clicked
+        if !old_model.same(model) {
+            ctx.request_paint();
+        }
     }
 
     fn layout(
@@ -135,77 +283,130 @@ impl Widget<ViewModel> for GoBoardWidget {
         let ViewModel { game, .. } = model;
 
         let widget_size = ctx.size();
-        let board_size = widget_size.min_side() - 2.0 * model.padding;
-        let stone_size = board_size / (game.size as f64);
+        let BoardSize { width, height } = game.size;
+        let (stone_size, _) = model.geometry(widget_size);
+        let board_size = stone_size * width.max(height) as f64;
         let line_stroke_style = StrokeStyle::new()
             .line_cap(LineCap::Round)
             .line_join(LineJoin::Round);
 
-        for x in 0..game.size {
-            for y in 0..game.size {
-                ctx.stroke_styled(
-                    Line::new(
-                        model.project(widget_size, (x, 0).into()),
-                        model.project(widget_size, (x, game.size - 1).into()),
-                    ),
-                    &Color::BLACK,
-                    board_size / 500.0,
-                    &line_stroke_style,
-                );
-                ctx.stroke_styled(
-                    Line::new(
-                        model.project(widget_size, (0, y).into()),
-                        model.project(widget_size, (game.size - 1, y).into()),
-                    ),
-                    &Color::BLACK,
-                    board_size / 500.0,
-                    &line_stroke_style,
-                );
+        let mut stroke_line = |from: Point, to: Point| {
+            ctx.stroke_styled(
+                Line::new(from, to),
+                &Color::BLACK,
+                board_size / 500.0,
+                &line_stroke_style,
+            );
+        };
+        match game.topology() {
+            Topology::Graph(neighbors) => {
+                for (i, neighbors) in neighbors.iter().enumerate() {
+                    for &n in neighbors.iter().filter(|&&n| n > i) {
+                        stroke_line(
+                            model.project(widget_size, game.size.pos(i)),
+                            model.project(widget_size, game.size.pos(n)),
+                        );
+                    }
+                }
+            }
+            topology => {
+                // lines that wrap around run off the board, to be continued on the other side
+                let overhang = |wraps: bool| if wraps { stone_size / 2.0 } else { 0.0 };
+                let dx = Vec2::new(overhang(topology.wraps_horizontally()), 0.0);
+                let dy = Vec2::new(0.0, overhang(topology.wraps_vertically()));
+
+                for x in 0..width {
+                    stroke_line(
+                        model.project(widget_size, (x, 0).into()) - dy,
+                        model.project(widget_size, (x, height - 1).into()) + dy,
+                    );
+                }
+                for y in 0..height {
+                    stroke_line(
+                        model.project(widget_size, (0, y).into()) - dx,
+                        model.project(widget_size, (width - 1, y).into()) + dx,
+                    );
+                }
             }
         }
 
         let stone_stroke_width = board_size / 250.0;
-        for x in 0..game.size {
-            for y in 0..game.size {
-                match game.stone_at(Pos(x as i32, y as i32)) {
-                    Some(color) => {
-                        let shape = Circle::new(
-                            model.project(widget_size, (x, y).into()),
-                            stone_size / 2.0 - stone_stroke_width / 3.0,
-                        );
-                        ctx.fill(
-                            shape,
-                            match color {
-                                Stone::Black => &Color::BLACK,
-                                Stone::White => &Color::WHITE,
-                            },
-                        );
-                        ctx.stroke_styled(
-                            shape,
-                            &Color::BLACK,
-                            stone_stroke_width,
-                            &line_stroke_style,
-                        );
-                    }
-                    None => {}
+        for p in game.size.points() {
+            match game.stone_at(p) {
+                Some(color) => {
+                    // dead stones are faded while scoring
+                    let alpha = if model.is_scoring() && model.marking.dead.contains(&p) {
+                        0.4
+                    } else {
+                        1.0
+                    };
+                    let shape = Circle::new(
+                        model.project(widget_size, p),
+                        stone_size / 2.0 - stone_stroke_width / 3.0,
+                    );
+                    ctx.fill(shape, &stone_color(color).with_alpha(alpha));
+                    ctx.stroke_styled(
+                        shape,
+                        &Color::BLACK.with_alpha(alpha),
+                        stone_stroke_width,
+                        &line_stroke_style,
+                    );
+                }
+                None => {}
+            }
+        }
+
+        if self.atari_hints && game.status == GameStatus::Playing {
+            for color in [Stone::Black, Stone::White] {
+                for p in game.groups_in_atari(color).into_iter().flatten() {
+                    let shape = Circle::new(model.project(widget_size, p), stone_size / 2.0);
+                    ctx.stroke(shape, &ATARI_COLOR, stone_stroke_width * 2.0);
+                }
+            }
+
+            let hovered = model.hover.and_then(|p| Some((p, game.stone_at(p)?)));
+            if let Some((p, color)) = hovered {
+                let liberties = game.liberties(p).unwrap();
+                for &liberty in &liberties {
+                    let shape = Circle::new(model.project(widget_size, liberty), stone_size / 6.0);
+                    ctx.stroke(shape, &ATARI_COLOR, stone_stroke_width);
+                }
+
+                let layout = ctx
+                    .text()
+                    .new_text_layout(liberties.len().to_string())
+                    .font(FontFamily::SYSTEM_UI, stone_size / 2.0)
+                    .text_color(stone_color(-color))
+                    .build()
+                    .unwrap();
+                let origin = model.project(widget_size, p) - layout.size().to_vec2() / 2.0;
+                ctx.draw_text(&layout, origin);
+            }
+        }
+
+        if model.is_scoring() {
+            let score = model.marking.score(game);
+            for p in game.size.points() {
+                if let Some(owner) = score.owner(p, game.size) {
+                    let shape = Rect::from_center_size(
+                        model.project(widget_size, p),
+                        (stone_size / 3.0, stone_size / 3.0),
+                    );
+                    ctx.fill(shape, &stone_color(owner));
+                    ctx.stroke(shape, &Color::BLACK, stone_stroke_width / 2.0);
                 }
             }
         }
 
         if let Some(p) = model.hover {
             let scale = 1.15;
-            if !game.has_stone_at(p) {
+            // only where a stone could go
+            if game.is_legal(p) {
                 let shape = Circle::new(
                     model.project(widget_size, p),
                     stone_size / 2.0 * scale - stone_stroke_width / 3.0,
                 );
-                ctx.fill(
-                    shape,
-                    match model.game.turn {
-                        Stone::Black => &Color::BLACK,
-                        Stone::White => &Color::WHITE,
-                    },
-                );
+                ctx.fill(shape, &stone_color(model.game.turn));
                 ctx.stroke_styled(
                     shape,
                     &Color::BLACK,
@@ -217,8 +418,169 @@ impl Widget<ViewModel> for GoBoardWidget {
     }
 }
 
+/// Of the rings around stones in atari, and the marks on the liberties of the chain under the mouse.
+const ATARI_COLOR: Color = Color::rgb8(0xd0, 0x20, 0x20);
+
+fn stone_color(color: Stone) -> Color {
+    match color {
+        Stone::Black => Color::BLACK,
+        Stone::White => Color::WHITE,
+    }
+}
+
+/// Who is playing, how far the game has come, and why the last click did nothing, if it didn't.
+fn sidebar_text(model: &ViewModel) -> String {
+    let game = &model.game;
+    let info = &game.info;
+
+    let mut text = String::new();
+    for header in [&info.event, &info.date] {
+        if !header.is_empty() {
+            text += &format!("{}\n", header);
+        }
+    }
+    if !text.is_empty() {
+        text.push('\n');
+    }
+
+    for color in [Stone::Black, Stone::White] {
+        text += info.name(color);
+        let rank = &info.players[color].rank;
+        if !rank.is_empty() {
+            text += &format!(" ({})", rank);
+        }
+        // the stones of the other color this player took
+        text += &format!("\n{} prisoners\n\n", game.state.captures[-color]);
+    }
+
+    text += &format!("Komi {}", info.komi);
+    if info.handicap > 0 {
+        text += &format!(", handicap {}", info.handicap);
+    }
+    text += &format!("\nMove {}", game.move_number());
+    if let Some((color, mv)) = game.tree().get(game.current()).mv {
+        // GTP cannot name the columns of the widest boards
+        let mv = Notation::Gtp
+            .format_move(mv, game.size)
+            .or_else(|_| Notation::Numeric.format_move(mv, game.size))
+            .unwrap_or_default();
+        text += &format!(": {} {}", color, mv);
+    }
+
+    let siblings = game.siblings();
+    if siblings.len() > 1 {
+        let i = siblings
+            .iter()
+            .position(|&id| id == game.current())
+            .unwrap();
+        text += &format!("\n\nVariation {} of {}", i + 1, siblings.len());
+    }
+    if model.is_scoring() {
+        let score = model.marking.score(game);
+        let method = game.ruleset.scoring();
+        text += "\n\nClick the dead stones\n";
+        for color in [Stone::Black, Stone::White] {
+            let accepted = if model.marking.accepted[color] {
+                ", accepted"
+            } else {
+                ""
+            };
+            text += &format!(
+                "\n{} {}{}",
+                info.name(color),
+                score.total(color, method),
+                accepted
+            );
+        }
+        text += &format!("\n{}", score.result(method));
+    } else if game.status == GameStatus::Playing {
+        let score = model.estimate.score(game);
+        text += &format!(
+            "\n\nScore estimate {}",
+            score.result(game.ruleset.scoring())
+        );
+    }
+    if !model.is_scoring() && !info.result.is_empty() {
+        text += &format!("\n\nResult {}", info.result);
+    }
+    if let Some(illegal_move) = model.illegal_move {
+        text += &format!("\n\nIllegal move:\n{}", illegal_move);
+    }
+    if model.thinking {
+        text += "\n\nThinking...";
+    }
+    text
+}
+
+fn play_buttons() -> impl Widget<ViewModel> {
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(
+            Button::new("Pass").on_click(|ctx, model: &mut ViewModel, _env| {
+                if model.thinking {
+                    return;
+                }
+                model.illegal_move = model.game.play(Move::Pass).err();
+                model.position_changed();
+                model.computer_moves(ctx.get_external_handle());
+            }),
+        )
+        .with_spacer(8.0)
+        .with_child(computer_button())
+}
+
+/// Switches the side the computer plays between neither, Black and White, and lets it move right away when
+/// it is its turn.
+fn computer_button() -> impl Widget<ViewModel> {
+    Button::dynamic(|model: &ViewModel, _env: &_| {
+        let side = match model.computer {
+            None => "nobody",
+            Some(Stone::Black) => "Black",
+            Some(Stone::White) => "White",
+        };
+        format!("Computer plays {}", side)
+    })
+    .on_click(|ctx, model: &mut ViewModel, _env| {
+        model.computer = match model.computer {
+            None => Some(Stone::Black),
+            Some(Stone::Black) => Some(Stone::White),
+            Some(Stone::White) => None,
+        };
+        model.computer_moves(ctx.get_external_handle());
+    })
+}
+
+/// Lets each player accept the score, or go back to playing when they disagree about which stones are dead.
+fn scoring_buttons() -> impl Widget<ViewModel> {
+    let accept = |color: Stone| {
+        let name = match color {
+            Stone::Black => "Black",
+            Stone::White => "White",
+        };
+        Button::new(format!("Accept for {}", name)).on_click(
+            move |_ctx, model: &mut ViewModel, _env| {
+                model.marking.accept(&mut model.game, color);
+            },
+        )
+    };
+
+    Flex::column()
+        .cross_axis_alignment(CrossAxisAlignment::Start)
+        .with_child(accept(Stone::Black))
+        .with_spacer(8.0)
+        .with_child(accept(Stone::White))
+        .with_spacer(8.0)
+        .with_child(
+            Button::new("Resume play").on_click(|ctx, model: &mut ViewModel, _env| {
+                model.game.resume();
+                model.marking = Marking::default();
+                model.computer_moves(ctx.get_external_handle());
+            }),
+        )
+}
+
 fn build_flex_ui() -> impl Widget<ViewModel> {
-    let board = GoBoardWidget::new();
+    let board = GoBoardWidget::new().atari_hints(true);
 
     FlexBox::new()
         .debug_label("parent")
@@ -234,18 +596,21 @@ fn build_flex_ui() -> impl Widget<ViewModel> {
                         .border(2.0)
                         .padding(16.0)
                         .grow(1.0)
-                        .content(LensWrap::new(
-                            Label::new(|state: &GameState, _env: &_| {
-                                format!(
-                                    "Captures:\n{} white\n{} black",
-                                    state.captures[Stone::White],
-                                    state.captures[Stone::Black]
+                        .content(
+                            Flex::column()
+                                .cross_axis_alignment(CrossAxisAlignment::Start)
+                                .with_child(
+                                    Label::new(|model: &ViewModel, _env: &_| sidebar_text(model))
+                                        .with_text_size(24.0)
+                                        .with_text_color(Color::BLACK),
                                 )
-                            })
-                            .with_text_size(24.0)
-                            .with_text_color(Color::BLACK),
-                            ViewModel::game.then(Game::state),
-                        )),
+                                .with_spacer(16.0)
+                                .with_child(Either::new(
+                                    |model: &ViewModel, _env: &_| model.is_scoring(),
+                                    scoring_buttons(),
+                                    play_buttons(),
+                                )),
+                        ),
                 ),
         )
         .with_child(
@@ -266,6 +631,61 @@ fn build_flex_ui() -> impl Widget<ViewModel> {
     // )
 }
 
+/// Plays the computer's moves when they come in.
+struct Delegate;
+
+impl AppDelegate<ViewModel> for Delegate {
+    fn command(
+        &mut self,
+        _ctx: &mut DelegateCtx,
+        _target: Target,
+        cmd: &Command,
+        model: &mut ViewModel,
+        _env: &Env,
+    ) -> Handled {
+        match cmd.get(COMPUTER_MOVE) {
+            Some(&(node, mv)) => {
+                model.computer_played(node, mv);
+                Handled::Yes
+            }
+            None => Handled::No,
+        }
+    }
+}
+
+/// Opens the SGF file given on the command line, if any, or starts a new game, on the topology given with
+/// `--topology`, e.g. `--topology torus`.
+fn load_game() -> Game {
+    let mut path = None;
+    let mut topology = Topology::Grid;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--topology" {
+            match args.next().map(|name| name.parse()) {
+                Some(Ok(parsed)) => topology = parsed,
+                _ => eprintln!("Unknown topology, expected grid, torus or cylinder"),
+            }
+        } else {
+            path = Some(arg);
+        }
+    }
+    let new_game = || Game::with_topology(13, Ruleset::default(), topology.clone());
+
+    match path {
+        Some(path) => match std::fs::read_to_string(&path)
+            .map_err(|err| err.to_string())
+            .and_then(|input| sgf::parse(&input).map_err(|err| err.to_string()))
+        {
+            Ok(game) => game,
+            Err(err) => {
+                eprintln!("Could not load {}: {}", path, err);
+                new_game()
+            }
+        },
+        None => new_game(),
+    }
+}
+
 pub fn main() -> Result<(), PlatformError> {
     let window = WindowDesc::new(build_flex_ui())
         .window_size((800., 600.))
@@ -273,10 +693,7 @@ pub fn main() -> Result<(), PlatformError> {
         .title("Go");
 
     AppLauncher::with_window(window)
+        .delegate(Delegate)
         .log_to_console()
-        .launch(ViewModel {
-            padding: 8.0,
-            game: Game::new(13),
-            hover: None,
-        })
+        .launch(ViewModel::new(load_game()))
 }
@@ -0,0 +1,601 @@
+//! A Monte Carlo tree search player. It grows a tree of continuations of the position by playing many random
+//! games, playouts, and picks the move that won the most of them.
+//!
+//! Going down the tree, moves are chosen with UCT, which weighs how often a move won against how little it was
+//! tried, mixed with RAVE: what a move scored in all the playouts where its player played on its point later
+//! on ("all moves as first", AMAF). That is a rough guess, but one that is there long before the move itself
+//! has been tried often.
+//!
+//! Several threads search the same tree, and the tree is kept from one move to the next as far as the game
+//! follows it.
+
+use crate::board::Board;
+use crate::estimate::{self, area_owners, sign};
+use crate::game::{Game, GameStatus, Move, Stone};
+use crate::player::{self, Player};
+use crate::zobrist;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How much thinking goes into a move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Budget {
+    /// This many playouts. With a single thread, the same position and seed then always give the same move.
+    Playouts(usize),
+    /// As many playouts as fit in this time.
+    Time(Duration),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Config {
+    pub budget: Budget,
+    /// The number of threads searching the tree together.
+    pub threads: usize,
+    /// The weight of UCT's term for moves that were tried little. RAVE already spreads out the search, so
+    /// this can be small.
+    pub exploration: f64,
+    /// The number of visits at which a move's own results count as much as its AMAF results.
+    pub rave_equivalence: f64,
+    /// Resign when the best move wins less than this share of its playouts, e.g. 0 never to resign.
+    pub resign_below: f64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            budget: Budget::Playouts(10_000),
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            exploration: 0.2,
+            rave_equivalence: 1000.0,
+            resign_below: 0.1,
+        }
+    }
+}
+
+/// Visits before a node gets children of its own, which keeps the tree from filling up with moves tried once.
+const EXPAND_AFTER: u32 = 4;
+
+/// How promising a move that was never tried looks, higher than any win rate so that each gets a try.
+const FIRST_PLAY_URGENCY: f64 = 1.1;
+
+/// Visits the best move needs before its win rate is trusted enough to resign on.
+const RESIGN_VISITS: u32 = 500;
+
+#[derive(Clone)]
+struct Node {
+    /// The point of the move leading here, `None` for a pass and for the root.
+    point: Option<usize>,
+    /// Who made that move.
+    color: Stone,
+    /// Empty until the node is expanded, and for the end of the game.
+    children: Vec<usize>,
+    expanded: bool,
+    /// Playouts through this node, including the ones still running. Until they end, those count as losses,
+    /// so that threads searching at the same time spread out over the tree.
+    visits: u32,
+    /// The share of the playouts that `color` won.
+    wins: f64,
+    /// Playouts below the parent in which `color` played on `point` first.
+    rave_visits: u32,
+    rave_wins: f64,
+}
+
+impl Node {
+    fn new(point: Option<usize>, color: Stone) -> Self {
+        Self {
+            point,
+            color,
+            children: vec![],
+            expanded: false,
+            visits: 0,
+            wins: 0.0,
+            rave_visits: 0,
+            rave_wins: 0.0,
+        }
+    }
+
+    fn win_rate(&self) -> f64 {
+        if self.visits == 0 {
+            0.0
+        } else {
+            self.wins / self.visits as f64
+        }
+    }
+
+    /// How much the player choosing between this node and its siblings wants to try it next.
+    fn urgency(&self, parent_visits: u32, config: &Config) -> f64 {
+        if self.visits == 0 && self.rave_visits == 0 {
+            return FIRST_PLAY_URGENCY;
+        }
+
+        let visits = self.visits as f64;
+        let value = if self.rave_visits == 0 {
+            self.win_rate()
+        } else {
+            let amaf = self.rave_wins / self.rave_visits as f64;
+            let beta = (config.rave_equivalence / (3.0 * visits + config.rave_equivalence)).sqrt();
+            (1.0 - beta) * self.win_rate() + beta * amaf
+        };
+        let exploration =
+            config.exploration * ((parent_visits.max(1) as f64).ln() / (visits + 1.0)).sqrt();
+        value + exploration
+    }
+}
+
+/// The position at the root of the tree, which does not change during a search.
+struct Root {
+    board: Board,
+    turn: Stone,
+    /// Whether the move leading to the root was a pass, so that another one ends the game.
+    passed: bool,
+    komi: f64,
+    /// `GameState::hash` of the position, to recognize it in a game.
+    key: u64,
+}
+
+struct Tree {
+    root: Root,
+    /// The root is the first node.
+    nodes: Vec<Node>,
+}
+
+impl Tree {
+    fn new(game: &Game) -> Self {
+        let passed = matches!(game.tree().get(game.current()).mv, Some((_, Move::Pass)));
+        Self {
+            root: Root {
+                board: game.state.board.clone(),
+                turn: game.turn,
+                passed,
+                komi: game.info.komi,
+                key: game.hash(),
+            },
+            nodes: vec![Node::new(None, -game.turn)],
+        }
+    }
+
+    /// The tree below the root's child for `mv`, if it has one.
+    fn advance(&self, mv: Move) -> Option<Self> {
+        let point = match mv {
+            Move::Place(p) => Some(p.index(self.root.board.size())?),
+            Move::Pass => None,
+            Move::Resign => return None,
+        };
+        let child = self.nodes[0]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].point == point)?;
+
+        let mut board = self.root.board.clone();
+        match point {
+            Some(i) => {
+                board.play(i, self.root.turn);
+            }
+            None => board.pass(),
+        }
+        let turn = -self.root.turn;
+        let key = board.hash() ^ zobrist::turn(turn);
+
+        // copy the subtree, numbering its nodes anew
+        let mut nodes = vec![self.nodes[child].clone()];
+        let mut k = 0;
+        while k < nodes.len() {
+            let children = std::mem::take(&mut nodes[k].children);
+            for old in children {
+                nodes.push(self.nodes[old].clone());
+                let new = nodes.len() - 1;
+                nodes[k].children.push(new);
+            }
+            k += 1;
+        }
+        nodes[0].point = None;
+
+        Some(Self {
+            root: Root {
+                board,
+                turn,
+                passed: point.is_none(),
+                komi: self.root.komi,
+                key,
+            },
+            nodes,
+        })
+    }
+}
+
+/// Adds the moves worth trying on `board` as children of `id`: the reasonable moves of the random games, and
+/// passing. `legal` rules out more moves, e.g. by superko at the root.
+fn expand(nodes: &mut Vec<Node>, id: usize, board: &Board, legal: impl Fn(usize) -> bool) {
+    let color = -nodes[id].color;
+    let first = nodes.len();
+    for i in 0..board.len() {
+        if board[i].is_none() && estimate::is_reasonable(board, i, color) && legal(i) {
+            nodes.push(Node::new(Some(i), color));
+        }
+    }
+    nodes.push(Node::new(None, color));
+    nodes[id].children = (first..nodes.len()).collect();
+    nodes[id].expanded = true;
+}
+
+/// Goes down the tree from the root and plays a random game from where it ends, then counts the result in
+/// every node on the way.
+fn simulate(root: &Root, nodes: &Mutex<Vec<Node>>, config: &Config, rng: &mut u64) {
+    let mut board = root.board.clone();
+    let mut path = vec![0];
+    let mut passes = usize::from(root.passed);
+
+    {
+        let mut nodes = nodes.lock().unwrap();
+        nodes[0].visits += 1;
+        let mut id = 0;
+        loop {
+            if !nodes[id].expanded {
+                if passes >= 2 || nodes[id].visits < EXPAND_AFTER {
+                    break;
+                }
+                expand(&mut nodes, id, &board, |_| true);
+            }
+            let parent_visits = nodes[id].visits;
+            let Some(child) = nodes[id].children.iter().copied().max_by(|&a, &b| {
+                let (a, b) = (&nodes[a], &nodes[b]);
+                a.urgency(parent_visits, config)
+                    .total_cmp(&b.urgency(parent_visits, config))
+            }) else {
+                break;
+            };
+
+            let node = &mut nodes[child];
+            node.visits += 1;
+            match node.point {
+                Some(i) => {
+                    board.play(i, node.color);
+                    passes = 0;
+                }
+                None => {
+                    board.pass();
+                    passes += 1;
+                }
+            }
+            path.push(child);
+            id = child;
+        }
+    }
+
+    let turn = if path.len() % 2 == 1 {
+        root.turn
+    } else {
+        -root.turn
+    };
+    // two passes in a row end the game, which is then scored as it stands, with territory of any size
+    let played = if passes >= 2 {
+        vec![]
+    } else {
+        estimate::playout(&mut board, turn, rng)
+    };
+    let score: f64 = area_owners(&board)
+        .into_iter()
+        .flatten()
+        .map(sign)
+        .sum::<f64>()
+        - root.komi;
+    let black = if score > 0.0 {
+        1.0
+    } else if score < 0.0 {
+        0.0
+    } else {
+        0.5
+    };
+    let result = |color: Stone| match color {
+        Stone::Black => black,
+        Stone::White => 1.0 - black,
+    };
+
+    // who played on each point first, from the node being updated on
+    let mut first = vec![None; board.len()];
+    for &(i, color) in played.iter().rev() {
+        first[i] = Some(color);
+    }
+
+    let mut nodes = nodes.lock().unwrap();
+    for &id in path.iter().rev() {
+        let color = nodes[id].color;
+        nodes[id].wins += result(color);
+        for k in 0..nodes[id].children.len() {
+            let child = nodes[id].children[k];
+            let child = &mut nodes[child];
+            if child.point.is_some_and(|i| first[i] == Some(-color)) {
+                child.rave_visits += 1;
+                child.rave_wins += result(-color);
+            }
+        }
+        if let Some(i) = nodes[id].point {
+            first[i] = Some(color);
+        }
+    }
+}
+
+pub struct MctsPlayer {
+    pub config: Config,
+    /// `None` until the first search, and after the game left the tree.
+    tree: Option<Tree>,
+    /// State of an xorshift generator, which seeds the threads.
+    seed: u64,
+}
+
+impl MctsPlayer {
+    /// A player seeded from the clock.
+    pub fn new(config: Config) -> Self {
+        Self::with_seed(config, player::clock_seed())
+    }
+
+    pub fn with_seed(config: Config, seed: u64) -> Self {
+        Self {
+            config,
+            tree: None,
+            seed: seed | 1,
+        }
+    }
+
+    /// Keeps the part of the tree below `mv`, for when `mv` was played in the position the last search was
+    /// about. Returns whether there was anything to keep; if not, the next search starts from scratch.
+    pub fn advance(&mut self, mv: Move) -> bool {
+        self.tree = self.tree.as_ref().and_then(|tree| tree.advance(mv));
+        self.tree.is_some()
+    }
+
+    /// Forgets the tree.
+    pub fn reset(&mut self) {
+        self.tree = None;
+    }
+
+    /// Moves the tree along with the moves played in `game` since the position at its root, or drops it when
+    /// `game` is not in a position that follows from there.
+    fn follow(&mut self, game: &Game) {
+        let Some(key) = self.tree.as_ref().map(|tree| tree.root.key) else {
+            return;
+        };
+        let nodes = game.tree();
+        let mut moves = vec![];
+        for id in nodes.ancestors(game.current()) {
+            let node = nodes.get(id);
+            if node.state.hash(node.turn) == key {
+                for mv in moves.into_iter().rev() {
+                    self.advance(mv);
+                }
+                break;
+            }
+            match node.mv {
+                Some((_, mv)) => moves.push(mv),
+                None => self.reset(),
+            }
+        }
+        if self
+            .tree
+            .as_ref()
+            .is_some_and(|tree| tree.root.key != game.hash())
+        {
+            self.reset();
+        }
+    }
+
+    /// Searches the current position of `game` for as long as the budget allows, and returns the best move.
+    pub fn search(&mut self, game: &Game) -> Move {
+        if game.status != GameStatus::Playing {
+            return Move::Pass;
+        }
+        self.follow(game);
+        let tree = self.tree.get_or_insert_with(|| Tree::new(game));
+        tree.root.komi = game.info.komi;
+
+        let Tree { root, nodes } = tree;
+        if !nodes[0].expanded {
+            let size = game.size;
+            expand(nodes, 0, &root.board, |i| game.is_legal(size.pos(i)));
+        }
+
+        let nodes = Mutex::new(std::mem::take(nodes));
+        let config = &self.config;
+        let (start, started) = (Instant::now(), AtomicUsize::new(0));
+        let more = || match config.budget {
+            Budget::Playouts(playouts) => started.fetch_add(1, Ordering::Relaxed) < playouts,
+            Budget::Time(time) => start.elapsed() < time,
+        };
+
+        thread::scope(|scope| {
+            for _ in 0..config.threads.max(1) {
+                let mut rng = estimate::random(&mut self.seed) | 1;
+                let (root, nodes, more) = (&*root, &nodes, &more);
+                scope.spawn(move || {
+                    while more() {
+                        simulate(root, nodes, config, &mut rng);
+                    }
+                });
+            }
+        });
+        tree.nodes = nodes.into_inner().unwrap();
+
+        self.best_move(game)
+    }
+
+    /// The root's most visited move that is legal in `game`, or resigning when even that one is lost.
+    fn best_move(&self, game: &Game) -> Move {
+        let Some(tree) = &self.tree else {
+            return Move::Pass;
+        };
+        // passing is not allowed before two handicap stones are down
+        let may_pass = game.legal_moves().contains(&Move::Pass);
+        let best = tree.nodes[0]
+            .children
+            .iter()
+            .map(|&child| &tree.nodes[child])
+            .filter(|node| {
+                node.point
+                    .map_or(may_pass, |i| game.is_legal(game.size.pos(i)))
+            })
+            .max_by_key(|node| node.visits);
+
+        match best {
+            Some(node)
+                if node.visits >= RESIGN_VISITS && node.win_rate() < self.config.resign_below =>
+            {
+                Move::Resign
+            }
+            Some(node) => node
+                .point
+                .map_or(Move::Pass, |i| Move::Place(game.size.pos(i))),
+            None => Move::Pass,
+        }
+    }
+}
+
+/// Plays the move it searched, and keeps the tree below it for the next one.
+impl Player for MctsPlayer {
+    fn genmove(&mut self, game: &Game) -> Move {
+        let mv = self.search(game);
+        self.advance(mv);
+        mv
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::game;
+    use crate::game::Pos;
+    use crate::rules::Ruleset;
+
+    fn player(playouts: usize, seed: u64) -> MctsPlayer {
+        MctsPlayer::with_seed(
+            Config {
+                budget: Budget::Playouts(playouts),
+                threads: 1,
+                resign_below: 0.0,
+                ..Config::default()
+            },
+            seed,
+        )
+    }
+
+    #[test]
+    fn takes_the_last_dame_when_passing_would_lose() {
+        let mut game = game(
+            "
+            . . X O . . .
+            . . X O . . .
+            . X . O . . .
+            . . X O . . .
+            . . X O . . .
+            ",
+        );
+        // Black has 14 points against White's 20, and the dame at C3 decides the game
+        game.info.komi = -5.5;
+        game.set_turn(Stone::White);
+        game.play(Move::Pass).unwrap();
+
+        for seed in 1..=5 {
+            assert_eq!(
+                player(3000, seed).search(&game),
+                Move::Place(Pos(2, 2)),
+                "{}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn takes_a_big_capture() {
+        let mut game = game(
+            "
+            . . . . .
+            . X X X .
+            X O O O X
+            . X . X .
+            . . . . .
+            ",
+        );
+        // Black only wins by taking the three stones
+        game.info.komi = 15.5;
+
+        let mv = player(2000, 1).search(&game);
+        assert_eq!(mv, Move::Place(Pos(2, 3)));
+        assert_eq!(player(2000, 1).search(&game), mv);
+    }
+
+    #[test]
+    fn the_tree_follows_the_game() {
+        let mut game = Game::new(5);
+        let mut player = player(2000, 3);
+
+        let mv = player.genmove(&game);
+        game.play(mv).unwrap();
+        let tree = player.tree.as_ref().unwrap();
+        assert_eq!(tree.root.key, game.hash());
+        let visits = tree.nodes[0].visits;
+        assert!(visits > 0);
+
+        // the reply that was searched the most is kept, with what was searched below it
+        let reply = tree.nodes[0]
+            .children
+            .iter()
+            .map(|&child| &tree.nodes[child])
+            .max_by_key(|node| node.visits)
+            .unwrap();
+        let (reply, reply_visits) = (reply.point.unwrap(), reply.visits);
+        game.play(Move::Place(game.size.pos(reply))).unwrap();
+        player.follow(&game);
+        let tree = player.tree.as_ref().unwrap();
+        assert_eq!(tree.root.key, game.hash());
+        assert_eq!(tree.nodes[0].visits, reply_visits);
+
+        // a position the tree does not lead to drops it
+        game.first();
+        game.play(Move::Pass).unwrap();
+        player.follow(&game);
+        assert!(player.tree.is_none());
+        assert!(!player.advance(Move::Pass));
+    }
+
+    #[test]
+    fn moves_ruled_out_by_superko_are_never_chosen() {
+        let mut game = game(
+            "
+            . X O . .
+            X O . O .
+            . X O . .
+            . . . . .
+            . . . . .
+            ",
+        );
+        game.ruleset = Ruleset::Chinese;
+        game.play(Move::Place(Pos(2, 1))).unwrap();
+        game.play(Move::Pass).unwrap();
+        game.play(Move::Pass).unwrap();
+        game.resume();
+
+        // the passes cleared the ko, so only superko forbids White to retake
+        let retake = Pos(1, 1);
+        assert!(!game.is_legal(retake));
+        let mut tree = Tree::new(&game);
+        expand(&mut tree.nodes, 0, &tree.root.board.clone(), |_| true);
+        let i = retake.index(game.size).unwrap();
+        let child = tree.nodes[0]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| tree.nodes[child].point == Some(i))
+            .unwrap();
+        tree.nodes[child].visits = 100;
+        tree.nodes[child].wins = 100.0;
+
+        let mut player = player(0, 1);
+        player.tree = Some(tree);
+        match player.best_move(&game) {
+            Move::Place(p) => assert!(game.is_legal(p), "{:?}", p),
+            mv => assert_eq!(mv, Move::Pass),
+        }
+    }
+}
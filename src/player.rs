@@ -0,0 +1,98 @@
+//! Computer players.
+
+use crate::estimate;
+use crate::game::{Game, Move, Pos, Stone};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Something that decides on moves, like a bot.
+pub trait Player {
+    /// The move for the side to move in `game`. Passes when the game is over.
+    fn genmove(&mut self, game: &Game) -> Move;
+}
+
+/// Plays a random legal move that does not fill one of its own eyes, and passes when there is none. Good for
+/// little more than testing.
+#[derive(Clone, Debug)]
+pub struct RandomPlayer {
+    /// State of an xorshift generator.
+    seed: u64,
+}
+
+impl RandomPlayer {
+    /// The same seed always gives the same moves.
+    pub fn new(seed: u64) -> Self {
+        Self { seed: seed | 1 }
+    }
+}
+
+/// Seeded from the clock, so that every game is different.
+impl Default for RandomPlayer {
+    fn default() -> Self {
+        Self::new(clock_seed())
+    }
+}
+
+impl Player for RandomPlayer {
+    fn genmove(&mut self, game: &Game) -> Move {
+        let candidates: Vec<Move> = game
+            .legal_moves()
+            .into_iter()
+            .filter(|&mv| matches!(mv, Move::Place(p) if !is_eye(game, p, game.turn)))
+            .collect();
+
+        if candidates.is_empty() {
+            Move::Pass
+        } else {
+            candidates[(estimate::random(&mut self.seed) % candidates.len() as u64) as usize]
+        }
+    }
+}
+
+/// A seed that differs from run to run, for players that should not repeat themselves.
+pub(crate) fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or(0)
+}
+
+/// Whether `p` is a point surrounded by `color` only, which `color` should not fill itself.
+pub fn is_eye(game: &Game, p: Pos, color: Stone) -> bool {
+    p.neighbors(game.size, game.topology())
+        .all(|n| game.stone_at(n) == Some(color))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::game;
+    use crate::game::GameStatus;
+
+    #[test]
+    fn random_moves_are_legal_and_leave_eyes_alone() {
+        let mut game = Game::new(9);
+        let mut player = RandomPlayer::new(7);
+        while game.status == GameStatus::Playing && game.move_number() < 300 {
+            let mv = player.genmove(&game);
+            if let Move::Place(p) = mv {
+                assert!(game.is_legal(p), "{:?}", p);
+                assert!(!is_eye(&game, p, game.turn), "{:?}", p);
+            }
+            game.play(mv).unwrap();
+        }
+    }
+
+    #[test]
+    fn passes_when_only_eyes_are_left() {
+        let game = game(
+            "
+            . X . X
+            X X X X
+            X X . X
+            X X X X
+        ",
+        );
+        assert_eq!(game.turn, Stone::Black);
+        assert_eq!(RandomPlayer::new(1).genmove(&game), Move::Pass);
+    }
+}
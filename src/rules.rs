@@ -0,0 +1,102 @@
+use crate::game::Stone;
+use crate::scoring::ScoringMethod;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Superko {
+    /// Only the immediate retake of a single-stone ko is forbidden.
+    None,
+    /// No move may recreate an earlier board position.
+    Positional,
+    /// No move may recreate an earlier board position with the same player to move.
+    Situational,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Ruleset {
+    #[default]
+    Japanese,
+    Chinese,
+    Aga,
+    NewZealand,
+    TrompTaylor,
+    Ing,
+}
+
+impl Ruleset {
+    pub const ALL: [Ruleset; 6] = [
+        Self::Japanese,
+        Self::Chinese,
+        Self::Aga,
+        Self::NewZealand,
+        Self::TrompTaylor,
+        Self::Ing,
+    ];
+
+    /// Whether a move may capture its own group, see `allows_single_stone_suicide` for a group of just that move.
+    pub fn allows_suicide(&self) -> bool {
+        match self {
+            Self::Japanese | Self::Chinese | Self::Aga => false,
+            Self::NewZealand | Self::TrompTaylor | Self::Ing => true,
+        }
+    }
+
+    /// Whether a move may capture itself when it does not join any stones of its own. Ing rules forbid this, as
+    /// it changes nothing but who is to move.
+    pub fn allows_single_stone_suicide(&self) -> bool {
+        self.allows_suicide() && *self != Self::Ing
+    }
+
+    pub fn superko(&self) -> Superko {
+        match self {
+            Self::Japanese => Superko::None,
+            Self::Chinese | Self::TrompTaylor => Superko::Positional,
+            Self::Aga | Self::NewZealand | Self::Ing => Superko::Situational,
+        }
+    }
+
+    pub fn first_player(&self) -> Stone {
+        Stone::Black
+    }
+
+    pub fn scoring(&self) -> ScoringMethod {
+        match self {
+            Self::Japanese => ScoringMethod::Territory,
+            _ => ScoringMethod::Area,
+        }
+    }
+}
+
+/// Formats the ruleset the way SGF's `RU` property names it.
+impl fmt::Display for Ruleset {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Japanese => "Japanese",
+            Self::Chinese => "Chinese",
+            Self::Aga => "AGA",
+            Self::NewZealand => "NZ",
+            Self::TrompTaylor => "Tromp-Taylor",
+            Self::Ing => "GOE",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Ruleset {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "japanese" | "jp" => Ok(Self::Japanese),
+            "chinese" | "cn" => Ok(Self::Chinese),
+            "aga" => Ok(Self::Aga),
+            "nz" | "new zealand" => Ok(Self::NewZealand),
+            "tromp-taylor" | "tromp taylor" | "tt" => Ok(Self::TrompTaylor),
+            "goe" | "ing" => Ok(Self::Ing),
+            _ => Err(()),
+        }
+    }
+}
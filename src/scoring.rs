@@ -0,0 +1,281 @@
+use crate::game::{BoardSize, Game, GameResult, Pos, Stone};
+use enum_map::EnumMap;
+use std::collections::HashSet;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScoringMethod {
+    /// Chinese style: stones on the board plus surrounded territory.
+    Area,
+    /// Japanese style: surrounded territory plus prisoners.
+    Territory,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Score {
+    pub komi: f64,
+    /// Live stones left on the board, per color.
+    pub stones: EnumMap<Stone, usize>,
+    /// Points surrounded by only one color, per owning color.
+    pub territory: EnumMap<Stone, usize>,
+    /// Stones taken off the board (captured during play or marked dead), per color of the taker.
+    pub prisoners: EnumMap<Stone, usize>,
+    /// Territory owner of every point that is empty or holds a dead stone, indexed like `GameState::board`.
+    pub ownership: Vec<Option<Stone>>,
+}
+
+impl Score {
+    pub fn area(&self, color: Stone) -> f64 {
+        (self.stones[color] + self.territory[color]) as f64 + self.komi_for(color)
+    }
+
+    pub fn territory(&self, color: Stone) -> f64 {
+        (self.territory[color] + self.prisoners[color]) as f64 + self.komi_for(color)
+    }
+
+    pub fn total(&self, color: Stone, method: ScoringMethod) -> f64 {
+        match method {
+            ScoringMethod::Area => self.area(color),
+            ScoringMethod::Territory => self.territory(color),
+        }
+    }
+
+    /// Black's total minus White's total, so positive means Black is ahead.
+    pub fn margin(&self, method: ScoringMethod) -> f64 {
+        self.total(Stone::Black, method) - self.total(Stone::White, method)
+    }
+
+    pub fn result(&self, method: ScoringMethod) -> GameResult {
+        let margin = self.margin(method);
+        if margin > 0.0 {
+            GameResult::Score {
+                winner: Stone::Black,
+                margin,
+            }
+        } else if margin < 0.0 {
+            GameResult::Score {
+                winner: Stone::White,
+                margin: -margin,
+            }
+        } else {
+            GameResult::Draw
+        }
+    }
+
+    pub fn owner(&self, p: Pos, size: BoardSize) -> Option<Stone> {
+        p.index(size).and_then(|i| self.ownership[i])
+    }
+
+    fn komi_for(&self, color: Stone) -> f64 {
+        match color {
+            Stone::White => self.komi,
+            Stone::Black => 0.0,
+        }
+    }
+}
+
+/// The dead stones the players mark after both passed, and whether each of them accepts the resulting score.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Marking {
+    pub dead: HashSet<Pos>,
+    pub accepted: EnumMap<Stone, bool>,
+}
+
+impl Marking {
+    /// Marks the chain at `p` dead, or alive again if it was dead. As this changes the score, both players have to accept
+    /// it anew. Returns whether there was a chain at `p`.
+    pub fn toggle(&mut self, game: &Game, p: Pos) -> bool {
+        let (_, chain) = match game.chain_at(p) {
+            Some(chain) => chain,
+            None => return false,
+        };
+
+        if self.dead.contains(&p) {
+            for p in chain {
+                self.dead.remove(&p);
+            }
+        } else {
+            self.dead.extend(chain);
+        }
+        self.accepted = EnumMap::default();
+        true
+    }
+
+    pub fn score(&self, game: &Game) -> Score {
+        score(game, &self.dead)
+    }
+
+    /// Accepts the score for `color`. Once both players have, the game finishes with it.
+    pub fn accept(&mut self, game: &mut Game, color: Stone) {
+        self.accepted[color] = true;
+        if self.accepted.values().all(|&accepted| accepted) {
+            let result = self.score(game).result(game.ruleset.scoring());
+            game.finish(result);
+        }
+    }
+}
+
+/// Scores the current position of `game` with its komi, treating the stones in `dead` as removed.
+pub fn score(game: &Game, dead: &HashSet<Pos>) -> Score {
+    let size = game.size;
+    let is_live = |p: Pos| game.has_stone_at(p) && !dead.contains(&p);
+
+    let mut stones = enum_map! { Stone::White => 0, Stone::Black => 0 };
+    let mut territory = enum_map! { Stone::White => 0, Stone::Black => 0 };
+    let mut prisoners = enum_map! {
+        Stone::White => game.state.captures[Stone::Black],
+        Stone::Black => game.state.captures[Stone::White],
+    };
+    let mut ownership = vec![None; size.len()];
+    let mut visited = vec![false; size.len()];
+
+    for (i, p) in size.points().enumerate() {
+        if let Some(color) = game.stone_at(p) {
+            if dead.contains(&p) {
+                prisoners[-color] += 1;
+            } else {
+                stones[color] += 1;
+                continue;
+            }
+        }
+
+        if visited[i] {
+            continue;
+        }
+
+        // flood the region of empty and dead points, noting which colors border it
+        let mut region: Vec<Pos> = vec![];
+        let mut borders: EnumMap<Stone, bool> = EnumMap::default();
+        let mut todo: Vec<Pos> = vec![p];
+        visited[i] = true;
+
+        while let Some(p) = todo.pop() {
+            region.push(p);
+
+            for np in p.neighbors(size, game.topology()) {
+                if is_live(np) {
+                    borders[game.stone_at(np).unwrap()] = true;
+                } else if let Some(j) = np.index(size) {
+                    if !visited[j] {
+                        visited[j] = true;
+                        todo.push(np);
+                    }
+                }
+            }
+        }
+
+        let owner = match (borders[Stone::Black], borders[Stone::White]) {
+            (true, false) => Some(Stone::Black),
+            (false, true) => Some(Stone::White),
+            _ => None,
+        };
+
+        if let Some(owner) = owner {
+            territory[owner] += region.len();
+            for p in region {
+                ownership[p.index(size).unwrap()] = Some(owner);
+            }
+        }
+    }
+
+    Score {
+        komi: game.info.komi,
+        stones,
+        territory,
+        prisoners,
+        ownership,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram;
+
+    fn game(diagram: &str, komi: f64) -> Game {
+        let mut game = diagram::game(diagram);
+        game.info.komi = komi;
+        game
+    }
+
+    /// The black stone at D2 is dead inside White's corner.
+    const POSITION: &str = "
+        . X O . .
+        . X O . .
+        X X O O O
+        . X O X .
+        . X O . .
+    ";
+
+    #[test]
+    fn area_and_territory_count_differently() {
+        let mut game = game(POSITION, 0.5);
+        // three white stones were captured during the game
+        game.state.captures[Stone::White] = 3;
+
+        let mut marking = Marking::default();
+        assert!(marking.toggle(&game, Pos(3, 3)));
+        let score = marking.score(&game);
+        assert_eq!(
+            score.stones,
+            enum_map! { Stone::Black => 6, Stone::White => 7 }
+        );
+        assert_eq!(
+            score.territory,
+            enum_map! { Stone::Black => 4, Stone::White => 8 }
+        );
+        assert_eq!(
+            score.prisoners,
+            enum_map! { Stone::Black => 3, Stone::White => 1 }
+        );
+
+        assert_eq!(score.area(Stone::Black), 10.0);
+        assert_eq!(score.area(Stone::White), 15.5);
+        assert_eq!(score.territory(Stone::Black), 7.0);
+        assert_eq!(score.territory(Stone::White), 9.5);
+        assert_eq!(
+            score.result(ScoringMethod::Area),
+            GameResult::Score {
+                winner: Stone::White,
+                margin: 5.5
+            }
+        );
+        assert_eq!(score.margin(ScoringMethod::Territory), -2.5);
+    }
+
+    #[test]
+    fn dead_stones_are_territory_of_their_captor() {
+        let game = game(POSITION, 6.5);
+        let size = game.size;
+
+        let alive = score(&game, &HashSet::new());
+        // while the black stone lives, the rest of the corner borders both colors
+        assert_eq!(alive.owner(Pos(4, 4), size), None);
+        assert_eq!(alive.owner(Pos(3, 3), size), None);
+        assert_eq!(alive.prisoners[Stone::White], 0);
+
+        let dead = score(&game, &HashSet::from([Pos(3, 3)]));
+        assert_eq!(dead.owner(Pos(3, 3), size), Some(Stone::White));
+        assert_eq!(dead.owner(Pos(4, 4), size), Some(Stone::White));
+        assert_eq!(dead.owner(Pos(0, 4), size), Some(Stone::Black));
+        // live stones are not territory
+        assert_eq!(dead.owner(Pos(1, 1), size), None);
+    }
+
+    #[test]
+    fn dame_belongs_to_no_one() {
+        let game = game(
+            "
+            X . O
+            X . O
+            X . O
+            ",
+            6.5,
+        );
+        let score = score(&game, &HashSet::new());
+        assert_eq!(score.territory, EnumMap::default());
+        assert!(score.ownership.iter().all(Option::is_none));
+        assert_eq!(score.area(Stone::Black), 3.0);
+        assert_eq!(score.area(Stone::White), 9.5);
+        assert_eq!(score.territory(Stone::White), 6.5);
+    }
+}
@@ -0,0 +1,611 @@
+use crate::coords::Notation;
+use crate::game::{BoardSize, Game, GameResult, GameStatus, IllegalMove, Move, Pos, Stone};
+use crate::topology::Topology;
+use crate::tree::{self, NodeId};
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SgfError {
+    /// The input is not well-formed SGF. Holds the byte offset at which parsing failed.
+    Syntax(usize),
+    InvalidValue {
+        property: String,
+        value: String,
+    },
+    /// A move in the record is not legal in the position it was played in. Counts from 1.
+    IllegalMove {
+        move_number: usize,
+        reason: IllegalMove,
+    },
+    /// `AB`, `AW` or `AE` after the game has started, which a `Game` cannot hold. Counts the moves before it.
+    SetupAfterMove {
+        property: String,
+        move_number: usize,
+    },
+    /// A board with more lines than SGF has coordinates for, which cannot be written.
+    BoardTooBig(BoardSize),
+    /// A board whose points are not connected like a grid's, which SGF has no property for.
+    UnsupportedTopology(Topology),
+}
+
+impl fmt::Display for SgfError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Syntax(offset) => write!(f, "SGF syntax error at offset {}", offset),
+            Self::InvalidValue { property, value } => {
+                write!(f, "invalid value for {}: [{}]", property, value)
+            }
+            Self::IllegalMove {
+                move_number,
+                reason,
+            } => write!(f, "illegal move {}: {}", move_number, reason),
+            Self::SetupAfterMove {
+                property,
+                move_number,
+            } => write!(
+                f,
+                "{} after move {} is not supported",
+                property, move_number
+            ),
+            Self::BoardTooBig(size) => {
+                write!(f, "SGF cannot describe a board of {}", size)
+            }
+            Self::UnsupportedTopology(topology) => {
+                write!(f, "SGF cannot describe a {} board", topology)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SgfError {}
+
+/// The most lines a board can have, as the coordinates only go up to `Z`.
+const MAX_LINES: usize = 52;
+
+/// A property identifier with its values, e.g. `AB[dd][pp]`.
+type Property = (String, Vec<String>);
+
+type Node = Vec<Property>;
+
+/// A sequence of nodes, followed by its variations.
+#[derive(Debug, Default)]
+struct GameTree {
+    nodes: Vec<Node>,
+    variations: Vec<GameTree>,
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            offset: 0,
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        while let Some(c) = self.input.get(self.offset) {
+            if c.is_ascii_whitespace() {
+                self.offset += 1;
+            } else {
+                return Some(*c);
+            }
+        }
+        None
+    }
+
+    fn expect(&mut self, c: u8) -> Result<(), SgfError> {
+        if self.peek() == Some(c) {
+            self.offset += 1;
+            Ok(())
+        } else {
+            Err(SgfError::Syntax(self.offset))
+        }
+    }
+
+    /// Parses the first game tree of a collection; any further games are ignored.
+    fn collection(&mut self) -> Result<GameTree, SgfError> {
+        // skip anything before the first game tree, like mail headers
+        while let Some(c) = self.input.get(self.offset) {
+            if *c == b'(' {
+                return self.tree();
+            }
+            self.offset += 1;
+        }
+        Err(SgfError::Syntax(self.offset))
+    }
+
+    fn tree(&mut self) -> Result<GameTree, SgfError> {
+        self.expect(b'(')?;
+
+        let mut tree = GameTree::default();
+        while self.peek() == Some(b';') {
+            tree.nodes.push(self.node()?);
+        }
+        if tree.nodes.is_empty() {
+            return Err(SgfError::Syntax(self.offset));
+        }
+        while self.peek() == Some(b'(') {
+            tree.variations.push(self.tree()?);
+        }
+
+        self.expect(b')')?;
+        Ok(tree)
+    }
+
+    fn node(&mut self) -> Result<Node, SgfError> {
+        self.expect(b';')?;
+
+        let mut node = vec![];
+        while let Some(c) = self.peek() {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            node.push(self.property()?);
+        }
+        Ok(node)
+    }
+
+    fn property(&mut self) -> Result<Property, SgfError> {
+        let mut ident = String::new();
+        while let Some(&c) = self.input.get(self.offset) {
+            if !c.is_ascii_alphabetic() {
+                break;
+            }
+            // old FF[3] files spell out identifiers like `AddBlack`; only the capitals count
+            if c.is_ascii_uppercase() {
+                ident.push(c as char);
+            }
+            self.offset += 1;
+        }
+
+        let mut values = vec![];
+        while self.peek() == Some(b'[') {
+            values.push(self.value()?);
+        }
+        if values.is_empty() {
+            return Err(SgfError::Syntax(self.offset));
+        }
+
+        Ok((ident, values))
+    }
+
+    fn value(&mut self) -> Result<String, SgfError> {
+        self.expect(b'[')?;
+
+        let mut value = vec![];
+        loop {
+            match self.input.get(self.offset) {
+                Some(b']') => break,
+                Some(b'\\') => {
+                    self.offset += 1;
+                    match self.input.get(self.offset) {
+                        // soft line break
+                        Some(b'\n') => {}
+                        Some(&c) => value.push(c),
+                        None => return Err(SgfError::Syntax(self.offset)),
+                    }
+                }
+                Some(&c) => value.push(c),
+                None => return Err(SgfError::Syntax(self.offset)),
+            }
+            self.offset += 1;
+        }
+        self.offset += 1;
+
+        String::from_utf8(value).map_err(|_| SgfError::Syntax(self.offset))
+    }
+}
+
+fn invalid(property: &str, value: &str) -> SgfError {
+    SgfError::InvalidValue {
+        property: property.to_string(),
+        value: value.to_string(),
+    }
+}
+
+fn parse_point(property: &str, value: &str, size: BoardSize) -> Result<Pos, SgfError> {
+    Notation::Sgf
+        .parse(value, size)
+        .map_err(|_| invalid(property, value))
+}
+
+/// Parses a point list value, which may be a compressed rectangle like `aa:cc`.
+fn parse_points(property: &str, value: &str, size: BoardSize) -> Result<Vec<Pos>, SgfError> {
+    match value.split_once(':') {
+        None => Ok(vec![parse_point(property, value, size)?]),
+        Some((from, to)) => {
+            let from = parse_point(property, from, size)?;
+            let to = parse_point(property, to, size)?;
+            let mut points = vec![];
+            for y in from.1.min(to.1)..=from.1.max(to.1) {
+                for x in from.0.min(to.0)..=from.0.max(to.0) {
+                    points.push(Pos(x, y));
+                }
+            }
+            Ok(points)
+        }
+    }
+}
+
+fn parse_move(property: &str, value: &str, size: BoardSize) -> Result<Move, SgfError> {
+    if value.is_empty() || (value == "tt" && size.width <= 19 && size.height <= 19) {
+        Ok(Move::Pass)
+    } else {
+        parse_point(property, value, size).map(Move::Place)
+    }
+}
+
+/// Parses an `SZ` value, which is either a single number for square boards or `width:height`.
+fn parse_size(value: &str) -> Option<BoardSize> {
+    let (width, height) = match value.split_once(':') {
+        Some((width, height)) => (width.parse().ok()?, height.parse().ok()?),
+        None => {
+            let size = value.parse().ok()?;
+            (size, size)
+        }
+    };
+
+    let valid = |lines: usize| (1..=MAX_LINES).contains(&lines);
+    (valid(width) && valid(height)).then_some(BoardSize { width, height })
+}
+
+fn parse_color(value: &str) -> Option<Stone> {
+    match value {
+        "B" | "b" => Some(Stone::Black),
+        "W" | "w" => Some(Stone::White),
+        _ => None,
+    }
+}
+
+/// Parses an `RE` value. Results we cannot represent, like wins on time or unknown results, give `None`.
+fn parse_result(value: &str) -> Option<GameResult> {
+    if value == "0" || value == "Draw" {
+        return Some(GameResult::Draw);
+    }
+
+    let (winner, rest) = value.split_once('+')?;
+    let winner = parse_color(winner)?;
+    match rest {
+        "R" | "Resign" => Some(GameResult::Resignation { winner }),
+        margin => margin
+            .parse()
+            .ok()
+            .map(|margin| GameResult::Score { winner, margin }),
+    }
+}
+
+/// Reads the first game in an SGF collection, with all its variations. The game is left at the end of the main line.
+pub fn parse(input: &str) -> Result<Game, SgfError> {
+    let tree = Parser::new(input).collection()?;
+
+    let root = &tree.nodes[0];
+    let size = match root.iter().find(|(ident, _)| ident == "SZ") {
+        Some((_, values)) => parse_size(&values[0]).ok_or_else(|| invalid("SZ", &values[0]))?,
+        None => BoardSize::square(19),
+    };
+    // unknown rulesets are played under our default rules
+    let ruleset = root
+        .iter()
+        .find(|(ident, _)| ident == "RU")
+        .and_then(|(_, values)| values[0].parse().ok())
+        .unwrap_or_default();
+
+    let mut game = Game::with_ruleset(size, ruleset);
+    game.info.komi = 0.0;
+    let mut result = None;
+
+    load_tree(&mut game, &tree, &mut result)?;
+
+    // White moves first in handicap games, which the record only shows once White has moved
+    let has_turn = root.iter().any(|(ident, _)| ident == "PL");
+    let started = !game.tree().get(tree::GameTree::ROOT).children.is_empty();
+    if game.info.handicap >= 2 && !has_turn && !started {
+        game.set_turn(Stone::White);
+    }
+
+    game.go_to(game.tree().main_line_end());
+    if let Some(result) = result {
+        game.status = GameStatus::Finished(result);
+    }
+
+    Ok(game)
+}
+
+/// Plays out `tree` from the current position of `game`, and each of its variations from where they branch off.
+fn load_tree(
+    game: &mut Game,
+    tree: &GameTree,
+    result: &mut Option<GameResult>,
+) -> Result<(), SgfError> {
+    for node in &tree.nodes {
+        load_node(game, node, result)?;
+    }
+
+    let branch = game.current();
+    for variation in &tree.variations {
+        game.go_to(branch);
+        load_tree(game, variation, result)?;
+    }
+
+    Ok(())
+}
+
+fn load_node(
+    game: &mut Game,
+    node: &Node,
+    result: &mut Option<GameResult>,
+) -> Result<(), SgfError> {
+    let size = game.size;
+
+    for (ident, values) in node {
+        let ident = ident.as_str();
+        match ident {
+            "KM" => {
+                game.info.komi = values[0].parse().map_err(|_| invalid(ident, &values[0]))?;
+            }
+            "HA" => {
+                game.info.handicap = values[0].parse().map_err(|_| invalid(ident, &values[0]))?;
+            }
+            "PB" => game.info.players[Stone::Black].name = values[0].clone(),
+            "PW" => game.info.players[Stone::White].name = values[0].clone(),
+            "BR" => game.info.players[Stone::Black].rank = values[0].clone(),
+            "WR" => game.info.players[Stone::White].rank = values[0].clone(),
+            "DT" => game.info.date = values[0].clone(),
+            "EV" => game.info.event = values[0].clone(),
+            "TM" => {
+                let time = values[0].parse().map_err(|_| invalid(ident, &values[0]))?;
+                game.info.time_limit = Some(time);
+            }
+            "OT" => game.info.overtime = values[0].clone(),
+            "AB" | "AW" | "AE" => {
+                let color = match ident {
+                    "AB" => Some(Stone::Black),
+                    "AW" => Some(Stone::White),
+                    _ => None,
+                };
+                for value in values {
+                    for p in parse_points(ident, value, size)? {
+                        if !game.setup(p, color) {
+                            return Err(SgfError::SetupAfterMove {
+                                property: ident.to_string(),
+                                move_number: game.move_number(),
+                            });
+                        }
+                    }
+                }
+            }
+            "PL" => {
+                let color = parse_color(&values[0]).ok_or_else(|| invalid(ident, &values[0]))?;
+                game.set_turn(color);
+            }
+            "B" | "W" => {
+                let mv = parse_move(ident, &values[0], size)?;
+                let move_number = game.move_number() + 1;
+
+                // the result is that of the record, which may well continue after both players passed
+                let recorded = std::mem::take(&mut game.info.result);
+                game.resume();
+                game.set_turn(parse_color(ident).unwrap());
+                let played = game.play(mv);
+                game.info.result = recorded;
+                played.map_err(|reason| SgfError::IllegalMove {
+                    move_number,
+                    reason,
+                })?;
+            }
+            "RE" => {
+                game.info.result = values[0].clone();
+                *result = parse_result(&values[0]);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn write_point(out: &mut String, p: Pos, size: BoardSize) {
+    // `write` made sure that the board is small enough
+    *out += &Notation::Sgf.format(p, size).unwrap();
+}
+
+fn write_escaped(out: &mut String, value: &str) {
+    for c in value.chars() {
+        if c == ']' || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+}
+
+/// Writes a text property, unless it is empty.
+fn write_text(out: &mut String, ident: &str, value: &str) {
+    if !value.is_empty() {
+        *out += ident;
+        out.push('[');
+        write_escaped(out, value);
+        out.push(']');
+    }
+}
+
+/// Writes the moves following `id` in the game tree, with each variation in parentheses.
+fn write_children(out: &mut String, tree: &tree::GameTree, id: NodeId, size: BoardSize) {
+    let children = &tree.get(id).children;
+    for &child in children {
+        if children.len() > 1 {
+            out.push('(');
+        }
+
+        let (color, mv) = tree.get(child).mv.unwrap();
+        *out += &format!(";{}[", color);
+        if let Move::Place(p) = mv {
+            write_point(out, p, size);
+        }
+        out.push(']');
+        write_children(out, tree, child, size);
+
+        if children.len() > 1 {
+            out.push(')');
+        }
+    }
+}
+
+/// Writes `game` as an SGF game record, with all its variations. Fails for boards with more than 52 lines, and for
+/// boards that are not grids, as reading the record back would connect their points differently.
+pub fn write(game: &Game) -> Result<String, SgfError> {
+    let size = game.size;
+    if size.width > MAX_LINES || size.height > MAX_LINES {
+        return Err(SgfError::BoardTooBig(size));
+    }
+    if *game.topology() != Topology::Grid {
+        return Err(SgfError::UnsupportedTopology(game.topology().clone()));
+    }
+    let info = &game.info;
+    let mut out = "(;GM[1]FF[4]CA[UTF-8]".to_string();
+    if size.is_square() {
+        out += &format!("SZ[{}]", size.width);
+    } else {
+        out += &format!("SZ[{}:{}]", size.width, size.height);
+    }
+    out += &format!("RU[{}]KM[{}]", game.ruleset, info.komi);
+
+    if info.handicap > 0 {
+        out += &format!("HA[{}]", info.handicap);
+    }
+    write_text(&mut out, "PB", &info.players[Stone::Black].name);
+    write_text(&mut out, "BR", &info.players[Stone::Black].rank);
+    write_text(&mut out, "PW", &info.players[Stone::White].name);
+    write_text(&mut out, "WR", &info.players[Stone::White].rank);
+    write_text(&mut out, "DT", &info.date);
+    write_text(&mut out, "EV", &info.event);
+    if let Some(time) = info.time_limit {
+        out += &format!("TM[{}]", time);
+    }
+    write_text(&mut out, "OT", &info.overtime);
+
+    let initial = game.initial_state();
+    for (ident, color) in [("AB", Stone::Black), ("AW", Stone::White)] {
+        let points: Vec<Pos> = (0..size.len())
+            .filter(|&i| initial.board[i] == Some(color))
+            .map(|i| size.pos(i))
+            .collect();
+
+        if !points.is_empty() {
+            out += ident;
+            for p in points {
+                out.push('[');
+                write_point(&mut out, p, size);
+                out.push(']');
+            }
+        }
+    }
+
+    // otherwise implied by the color of the first move
+    let root = game.tree().get(tree::GameTree::ROOT);
+    let first_color = root.children.first().and_then(|&id| game.tree().get(id).mv);
+    if first_color.map(|(color, _)| color) != Some(root.turn) {
+        out += &format!("PL[{}]", root.turn);
+    }
+
+    write_text(&mut out, "RE", &info.result);
+
+    write_children(&mut out, game.tree(), tree::GameTree::ROOT, size);
+
+    out.push_str(")\n");
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diagram::Diagram;
+    use crate::rules::Ruleset;
+
+    /// Parses `input`, writes it out and parses that again, which must give the same game.
+    fn round_trip(input: &str) -> Game {
+        let game = parse(input).unwrap();
+        let written = write(&game).unwrap();
+        let again = parse(&written).unwrap();
+        assert_eq!(write(&again).unwrap(), written);
+        assert!(again.tree() == game.tree());
+        assert_eq!(again.info.handicap, game.info.handicap);
+        again
+    }
+
+    #[test]
+    fn handicap_game_with_variations_round_trips() {
+        let game = round_trip(
+            "(;GM[1]FF[4]SZ[9]KM[0.5]HA[2]AB[cc][gg];W[ee];B[ec](;W[ce])(;W[ge];B[gc]))",
+        );
+        assert_eq!(game.info.handicap, 2);
+        assert_eq!(game.first_turn(), Stone::White);
+        assert_eq!(game.siblings().len(), 2);
+        assert_eq!(
+            Diagram::from_game(&game).to_string(),
+            ". . . . . . . . .\n\
+             . . . . . . . . .\n\
+             . . X . X . . . .\n\
+             . . . . . . . . .\n\
+             . .(O). O . . . .\n\
+             . . . . . . . . .\n\
+             . . . . . . X . .\n\
+             . . . . . . . . .\n\
+             . . . . . . . . .\n"
+        );
+    }
+
+    #[test]
+    fn player_to_move_round_trips() {
+        let game = round_trip("(;GM[1]FF[4]SZ[5]AB[aa]AW[bb]PL[W])");
+        assert_eq!(game.turn, Stone::White);
+        assert!(write(&game).unwrap().contains("PL[W]"));
+    }
+
+    #[test]
+    fn setup_after_a_move_is_refused() {
+        assert!(matches!(
+            parse("(;GM[1]FF[4]SZ[9];B[ee];AB[cc])"),
+            Err(SgfError::SetupAfterMove { move_number: 1, .. })
+        ));
+    }
+
+    #[test]
+    fn browsing_keeps_the_result() {
+        let mut game = parse("(;SZ[9]RE[W+3.5];B[ee];W[cc];B[];W[])").unwrap();
+        assert!(game.undo() && game.redo());
+        assert_eq!(game.status, GameStatus::Scoring);
+        assert_eq!(game.info.result, "W+3.5");
+        assert!(write(&game).unwrap().contains("RE[W+3.5]"));
+
+        game.resume();
+        assert!(game.info.result.is_empty());
+    }
+
+    #[test]
+    fn result_survives_moves_after_the_passes() {
+        let game = parse("(;SZ[9]RE[B+R];B[ee];W[];B[];W[cc])").unwrap();
+        assert_eq!(game.info.result, "B+R");
+    }
+
+    #[test]
+    fn boards_bigger_than_sgf_allows_are_refused() {
+        let mut game = Game::new(60);
+        game.play(Move::Place(Pos(55, 55))).unwrap();
+        assert_eq!(write(&game), Err(SgfError::BoardTooBig(game.size)));
+        assert_eq!(write(&Game::new((52, 19))).map(|_| ()), Ok(()));
+    }
+
+    #[test]
+    fn boards_other_than_grids_are_refused() {
+        let game = Game::with_topology(5, Ruleset::default(), Topology::Torus);
+        assert_eq!(
+            write(&game),
+            Err(SgfError::UnsupportedTopology(Topology::Torus))
+        );
+    }
+}
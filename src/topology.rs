@@ -0,0 +1,176 @@
+//! How the points of a board are connected, which decides everything about liberties, captures and ko.
+
+use crate::game::{BoardSize, Pos};
+use std::fmt;
+use std::str::FromStr;
+use std::sync::Arc;
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "lowercase"))]
+pub enum Topology {
+    /// The usual board, where points on the edge have fewer neighbors.
+    #[default]
+    Grid,
+    /// Both the left and right edges and the top and bottom edges are joined.
+    Torus,
+    /// Only the left and right edges are joined.
+    Cylinder,
+    /// Any connection of points: the neighbors of every point, by index. Made with `Topology::graph`.
+    Graph(Arc<Vec<Vec<usize>>>),
+}
+
+impl Topology {
+    /// A graph on the points of `size` where the two points of each edge are neighbors, or `None` if an edge
+    /// has a point off the board.
+    pub fn graph(size: BoardSize, edges: &[(Pos, Pos)]) -> Option<Self> {
+        let mut neighbors = vec![vec![]; size.len()];
+        for &(a, b) in edges {
+            let (a, b) = (a.index(size)?, b.index(size)?);
+            if a != b && !neighbors[a].contains(&b) {
+                neighbors[a].push(b);
+                neighbors[b].push(a);
+            }
+        }
+        Some(Self::Graph(Arc::new(neighbors)))
+    }
+
+    /// Whether the topology can connect the points of a board of `size`. Always true except for a graph, which
+    /// has to list the neighbors of exactly the points of the board, each neighbor once, and with every point a
+    /// neighbor of its neighbors, as `Topology::graph` makes it.
+    pub fn fits(&self, size: BoardSize) -> bool {
+        let Self::Graph(neighbors) = self else {
+            return true;
+        };
+        neighbors.len() == size.len()
+            && neighbors.iter().enumerate().all(|(i, list)| {
+                list.iter().enumerate().all(|(k, &n)| {
+                    n < neighbors.len()
+                        && n != i
+                        && !list[..k].contains(&n)
+                        && neighbors[n].contains(&i)
+                })
+            })
+    }
+
+    /// Whether going off the left edge comes back on the right.
+    pub fn wraps_horizontally(&self) -> bool {
+        matches!(self, Self::Torus | Self::Cylinder)
+    }
+
+    /// Whether going off the top edge comes back on the bottom.
+    pub fn wraps_vertically(&self) -> bool {
+        matches!(self, Self::Torus)
+    }
+
+    /// The neighbors of `p` on a board of `size`, none if `p` is off the board.
+    pub fn neighbors(&self, p: Pos, size: BoardSize) -> Vec<Pos> {
+        if !p.valid(size) {
+            return vec![];
+        }
+        if let Self::Graph(neighbors) = self {
+            let i = p.index(size).unwrap();
+            return neighbors[i].iter().map(|&n| size.pos(n)).collect();
+        }
+
+        let wrap = |c: i32, lines: usize, wraps: bool| {
+            if wraps {
+                c.rem_euclid(lines as i32)
+            } else {
+                c
+            }
+        };
+        let Pos(x, y) = p;
+        let mut neighbors = vec![];
+        for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+            let n = Pos(
+                wrap(x + dx, size.width, self.wraps_horizontally()),
+                wrap(y + dy, size.height, self.wraps_vertically()),
+            );
+            // on narrow boards, both ways around can lead to the same point, or back to `p`
+            if n.valid(size) && n != p && !neighbors.contains(&n) {
+                neighbors.push(n);
+            }
+        }
+        neighbors
+    }
+
+    /// The neighbors of every point of a board of `size`, by index.
+    pub fn neighbor_table(&self, size: BoardSize) -> Arc<Vec<Vec<usize>>> {
+        match self {
+            Self::Graph(neighbors) => neighbors.clone(),
+            _ => Arc::new(
+                size.points()
+                    .map(|p| {
+                        self.neighbors(p, size)
+                            .into_iter()
+                            .filter_map(|n| n.index(size))
+                            .collect()
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl fmt::Display for Topology {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Grid => "grid",
+            Self::Torus => "torus",
+            Self::Cylinder => "cylinder",
+            Self::Graph(_) => "graph",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Parses the name of a topology other than `Graph`, which needs its edges.
+impl FromStr for Topology {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "grid" => Ok(Self::Grid),
+            "torus" => Ok(Self::Torus),
+            "cylinder" => Ok(Self::Cylinder),
+            _ => Err(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::rules::Ruleset;
+
+    #[test]
+    fn graphs_fit_only_their_own_board() {
+        let size = BoardSize::square(2);
+        let ring =
+            Topology::graph(size, &[(Pos(0, 0), Pos(1, 0)), (Pos(1, 0), Pos(1, 1))]).unwrap();
+        assert!(ring.fits(size));
+        assert!(!ring.fits(BoardSize::square(3)));
+        assert!(Topology::Torus.fits(BoardSize::square(3)));
+
+        let graph = |neighbors: Vec<Vec<usize>>| Topology::Graph(Arc::new(neighbors));
+        let size = BoardSize {
+            width: 3,
+            height: 1,
+        };
+        assert!(graph(vec![vec![1], vec![0, 2], vec![1]]).fits(size));
+        // one-way, a loop, a neighbor twice, and one off the board
+        assert!(!graph(vec![vec![2], vec![0], vec![0]]).fits(size));
+        assert!(!graph(vec![vec![0], vec![], vec![]]).fits(size));
+        assert!(!graph(vec![vec![1, 1], vec![0, 0], vec![]]).fits(size));
+        assert!(!graph(vec![vec![3], vec![], vec![]]).fits(size));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not fit")]
+    fn games_refuse_a_graph_for_another_board() {
+        let ring = Topology::graph(BoardSize::square(2), &[]).unwrap();
+        Game::with_topology(3, Ruleset::default(), ring);
+    }
+}
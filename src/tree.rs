@@ -0,0 +1,283 @@
+use crate::game::{GameState, Move, Stone};
+
+pub type NodeId = usize;
+
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    pub parent: Option<NodeId>,
+    /// The first child continues the main line, the others are variations.
+    pub children: Vec<NodeId>,
+    /// Index into `children` of the line that was visited last, which `Game::redo` follows.
+    pub selected: usize,
+    /// The move leading to this node and who played it; `None` for the root.
+    pub mv: Option<(Stone, Move)>,
+    /// The position after the move.
+    pub state: GameState,
+    /// The player to move in this position.
+    pub turn: Stone,
+    /// Whether the player to move resigned here, which ends the game without a move of its own.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub resigned: bool,
+}
+
+impl Node {
+    pub fn selected_child(&self) -> Option<NodeId> {
+        self.children.get(self.selected).copied()
+    }
+}
+
+/// All the lines played in a game, starting from the initial position at the root.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(transparent))]
+pub struct GameTree {
+    nodes: Vec<Node>,
+}
+
+impl GameTree {
+    pub const ROOT: NodeId = 0;
+
+    pub fn new(state: GameState, turn: Stone) -> Self {
+        Self {
+            nodes: vec![Node {
+                parent: None,
+                children: vec![],
+                selected: 0,
+                mv: None,
+                state,
+                turn,
+                resigned: false,
+            }],
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn get(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub(crate) fn get_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id]
+    }
+
+    /// Whether the nodes link up the way `add_child` leaves them, with every node after its parent and every node but
+    /// the root reached by a move, e.g. after loading a saved tree.
+    #[cfg(feature = "serde")]
+    pub(crate) fn is_consistent(&self) -> bool {
+        let nodes = &self.nodes;
+        nodes.first().is_some_and(|root| root.parent.is_none())
+            && nodes.iter().enumerate().all(|(id, node)| {
+                let parent_ok = node.parent.map_or(id == Self::ROOT, |parent| {
+                    parent < id && nodes[parent].children.contains(&id)
+                });
+                let children_ok = node
+                    .children
+                    .iter()
+                    .all(|&child| child < nodes.len() && nodes[child].parent == Some(id));
+                parent_ok
+                    && children_ok
+                    && node.selected <= node.children.len().saturating_sub(1)
+                    && node.mv.is_some() == (id != Self::ROOT)
+            })
+    }
+
+    pub fn find_child(&self, id: NodeId, color: Stone, mv: Move) -> Option<NodeId> {
+        self.nodes[id]
+            .children
+            .iter()
+            .copied()
+            .find(|&child| self.nodes[child].mv == Some((color, mv)))
+    }
+
+    /// Adds a node for `mv` below `parent`, as a new variation if `parent` already has children.
+    pub fn add_child(
+        &mut self,
+        parent: NodeId,
+        color: Stone,
+        mv: Move,
+        state: GameState,
+        turn: Stone,
+    ) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            parent: Some(parent),
+            children: vec![],
+            selected: 0,
+            mv: Some((color, mv)),
+            state,
+            turn,
+            resigned: false,
+        });
+        self.nodes[parent].children.push(id);
+        id
+    }
+
+    /// The nodes from the root down to and including `id`.
+    pub fn path(&self, id: NodeId) -> Vec<NodeId> {
+        let mut path: Vec<NodeId> = self.ancestors(id).collect();
+        path.reverse();
+        path
+    }
+
+    /// `id` itself, followed by its parent, grandparent, and so on up to the root.
+    pub fn ancestors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        std::iter::successors(Some(id), move |&id| self.nodes[id].parent)
+    }
+
+    /// Number of moves from the root to `id`.
+    pub fn depth(&self, id: NodeId) -> usize {
+        self.ancestors(id).count() - 1
+    }
+
+    /// Marks the line through `id` as the one `Game::redo` follows, at every branch above it.
+    pub(crate) fn select(&mut self, id: NodeId) {
+        let mut child = id;
+        while let Some(parent) = self.nodes[child].parent {
+            let node = &mut self.nodes[parent];
+            node.selected = node.children.iter().position(|&c| c == child).unwrap();
+            child = parent;
+        }
+    }
+
+    /// Makes the line through `id` the main line, by moving it to the front at every branch above it.
+    pub fn promote(&mut self, id: NodeId) {
+        let mut child = id;
+        while let Some(parent) = self.nodes[child].parent {
+            let node = &mut self.nodes[parent];
+            let i = node.children.iter().position(|&c| c == child).unwrap();
+            node.children.remove(i);
+            node.children.insert(0, child);
+            node.selected = 0;
+            child = parent;
+        }
+    }
+
+    /// The last node of the main line.
+    pub fn main_line_end(&self) -> NodeId {
+        let mut id = Self::ROOT;
+        while let Some(&child) = self.nodes[id].children.first() {
+            id = child;
+        }
+        id
+    }
+
+    /// Removes `id` and everything below it. The root cannot be removed.
+    ///
+    /// Node ids change; the returned table maps every old id to its new one, or to `None` if it was removed.
+    pub fn remove(&mut self, id: NodeId) -> Vec<Option<NodeId>> {
+        let mut removed = vec![false; self.nodes.len()];
+        if let Some(parent) = self.nodes[id].parent {
+            let node = &mut self.nodes[parent];
+            node.children.retain(|&c| c != id);
+            node.selected = node.selected.min(node.children.len().saturating_sub(1));
+
+            let mut todo = vec![id];
+            while let Some(id) = todo.pop() {
+                removed[id] = true;
+                todo.extend(&self.nodes[id].children);
+            }
+        }
+
+        let mut remap = vec![None; self.nodes.len()];
+        let mut next = 0;
+        for (old, &removed) in removed.iter().enumerate() {
+            if !removed {
+                remap[old] = Some(next);
+                next += 1;
+            }
+        }
+
+        let nodes = std::mem::take(&mut self.nodes);
+        self.nodes = nodes
+            .into_iter()
+            .enumerate()
+            .filter(|&(old, _)| !removed[old])
+            .map(|(_, mut node)| {
+                node.parent = node.parent.and_then(|p| remap[p]);
+                for child in node.children.iter_mut() {
+                    *child = remap[*child].unwrap();
+                }
+                node
+            })
+            .collect();
+
+        remap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::{Game, Pos};
+
+    /// The root with the variations a-b, c-d and e below it, with ids 1 to 5 in that order.
+    fn tree() -> GameTree {
+        let state = Game::new(9).state;
+        let mut tree = GameTree::new(state.clone(), Stone::Black);
+        let mut add = |parent, x| {
+            tree.add_child(
+                parent,
+                Stone::Black,
+                Move::Place(Pos(x, 0)),
+                state.clone(),
+                Stone::White,
+            )
+        };
+        let a = add(GameTree::ROOT, 0);
+        add(a, 1);
+        let c = add(GameTree::ROOT, 2);
+        add(c, 3);
+        add(GameTree::ROOT, 4);
+        tree
+    }
+
+    #[test]
+    fn removing_a_branch_renumbers_the_nodes_after_it() {
+        let mut tree = tree();
+        tree.select(4);
+
+        let remap = tree.remove(3);
+        assert_eq!(remap, [Some(0), Some(1), Some(2), None, None, Some(3)]);
+        assert_eq!(tree.len(), 4);
+        assert_eq!(tree.get(GameTree::ROOT).children, [1, 3]);
+        assert_eq!(tree.get(GameTree::ROOT).selected, 1);
+        assert_eq!(tree.get(3).parent, Some(GameTree::ROOT));
+        assert_eq!(tree.get(3).mv, Some((Stone::Black, Move::Place(Pos(4, 0)))));
+        assert_eq!(tree.get(2).parent, Some(1));
+
+        assert_eq!(
+            tree.remove(GameTree::ROOT),
+            [Some(0), Some(1), Some(2), Some(3)]
+        );
+        assert_eq!(tree.len(), 4);
+    }
+
+    #[test]
+    fn promoting_moves_the_line_to_the_front() {
+        let mut tree = tree();
+        tree.promote(4);
+        assert_eq!(tree.get(GameTree::ROOT).children, [3, 1, 5]);
+        assert_eq!(tree.get(GameTree::ROOT).selected, 0);
+        assert_eq!(tree.main_line_end(), 4);
+    }
+
+    #[test]
+    fn selecting_marks_the_line_redo_follows() {
+        let mut tree = tree();
+        assert_eq!(tree.get(GameTree::ROOT).selected_child(), Some(1));
+        tree.select(5);
+        assert_eq!(tree.get(GameTree::ROOT).selected_child(), Some(5));
+        tree.select(2);
+        assert_eq!(tree.get(GameTree::ROOT).selected_child(), Some(1));
+        assert_eq!(tree.get(1).selected_child(), Some(2));
+        assert_eq!(tree.main_line_end(), 2);
+    }
+}
@@ -0,0 +1,47 @@
+//! Zobrist keys: a random number for every (point, color) pair, so that a position can be hashed by xor-ing
+//! the keys of its stones, and updated with a single xor whenever a stone is added or removed.
+
+use crate::game::Stone;
+
+/// The largest board SGF can describe is 52x52.
+pub const MAX_POINTS: usize = 52 * 52;
+
+/// Xor-ed into a position's hash when White is to move.
+pub const WHITE_TO_MOVE: u64 = splitmix(2 * MAX_POINTS as u64);
+
+static KEYS: [[u64; 2]; MAX_POINTS] = {
+    let mut keys = [[0; 2]; MAX_POINTS];
+    let mut i = 0;
+    while i < MAX_POINTS {
+        keys[i][0] = splitmix(2 * i as u64);
+        keys[i][1] = splitmix(2 * i as u64 + 1);
+        i += 1;
+    }
+    keys
+};
+
+/// The `n`th output of the SplitMix64 generator, which gives well-mixed keys that are the same on every run.
+const fn splitmix(n: u64) -> u64 {
+    let mut z = (n + 1).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+/// The key of a stone of `color` at point `i`. Boards bigger than SGF allows get the keys of their further points
+/// computed on the spot.
+pub fn stone(i: usize, color: Stone) -> u64 {
+    match KEYS.get(i) {
+        Some(keys) => keys[color as usize],
+        // skipping the key of the side to move
+        None => splitmix(2 * (i as u64 + 1) + color as u64),
+    }
+}
+
+/// The key of the side to move, which is zero for Black.
+pub fn turn(color: Stone) -> u64 {
+    match color {
+        Stone::Black => 0,
+        Stone::White => WHITE_TO_MOVE,
+    }
+}